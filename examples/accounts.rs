@@ -0,0 +1,37 @@
+use fyers_rust::accounts::AccountRegistry;
+use fyers_rust::error::FyersError;
+use std::env;
+
+#[tokio::main]
+async fn main() -> Result<(), FyersError> {
+    dotenvy::dotenv().expect(".env file not found");
+    let app_id = env::var("FYERS_APP_ID").expect("FYERS_APP_ID must be set in .env");
+
+    // FYERS_ACCOUNTS is a comma separated list of fy_id=access_token pairs
+    let accounts = env::var("FYERS_ACCOUNTS").expect("FYERS_ACCOUNTS must be set in .env");
+
+    let mut registry = AccountRegistry::new();
+    for entry in accounts.split(',') {
+        let (fy_id, access_token) = entry.split_once('=').expect("Expected fy_id=access_token");
+        registry.add_with_token(fy_id.trim(), app_id.clone(), access_token.trim().to_string());
+    }
+
+    println!("\n Fetching positions for {} accounts...", registry.len());
+    let positions = registry.positions().await;
+    for (fy_id, response) in positions.successes() {
+        println!("{:<10} open: {:>3} P&L: {:>12.2}", fy_id, response.overall.count_open, response.overall.pl_total);
+    }
+    for (fy_id, e) in positions.errors() {
+        eprintln!("{:<10} error: {}", fy_id, e);
+    }
+    println!("\n Total P&L across accounts: {:.2}", positions.pl_total());
+
+    let funds = registry.funds().await;
+    let total = funds.total_funds();
+    println!(
+        " Available balance across accounts: equity {:.2}, commodity {:.2}",
+        total.equity.available_balance, total.commodity.available_balance
+    );
+
+    Ok(())
+}
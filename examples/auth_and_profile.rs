@@ -12,11 +12,11 @@ async fn main() -> Result<(), FyersError> {
     let secret_key = env::var("FYERS_SECRET").expect("FYERS_SECRET must be set");
 
     let redirect_url = "https://wintersunset95.github.io";
-    let state = "sample_state";
 
     println!("\n Starting FYERS Authentication & Profile fetch \n");
 
-    let auth_url = auth::generate_auth_url(&client_id, redirect_url, state);
+    let session = auth::LoginSession::new(&client_id, redirect_url);
+    let auth_url = session.auth_url();
 
     println!("ACTION REQUIRED: Please complete the manual login process");
     println!("1. Copy the following URL and paste it into your browser:");
@@ -28,16 +28,9 @@ async fn main() -> Result<(), FyersError> {
     let mut redirect_url_input = String::new();
     io::stdin().read_line(&mut redirect_url_input).unwrap();
 
-    let auth_code = redirect_url_input
-        .split('&')
-        .find(|s| s.contains("auth_code="))
-        .and_then(|s| s.split('=').nth(1))
-        .expect("Cound not find 'auth_code' in the provided URL")
-        .to_string();
-    
-    println!("\n Auth code extracted. Requesting access token from FYERS...");
+    println!("\n Validating redirect and requesting access token from FYERS...");
 
-    let access_token = auth::generate_access_token(&client_id, &secret_key, &auth_code.trim()).await?;
+    let access_token = session.complete(&secret_key, &redirect_url_input).await?;
     println!("\n Access token generated. Fetching user profile...");
 
     println!("Fetching user profile...");
@@ -0,0 +1,26 @@
+use fyers_rust::error::FyersError;
+use fyers_rust::auth::LoginSession;
+use fyers_rust::login_server::{login_session_with_local_server, DEFAULT_LOGIN_TIMEOUT};
+use fyers_rust::user::User;
+use std::env;
+
+#[tokio::main]
+async fn main() -> Result<(), FyersError> {
+    dotenvy::dotenv().expect(".env file not found");
+    let client_id = env::var("FYERS_APP_ID").expect("FYERS_APP_ID must be set");
+    let secret_key = env::var("FYERS_SECRET").expect("FYERS_SECRET must be set");
+
+    // This must match the redirect URI registered for the app
+    let redirect_uri = "http://127.0.0.1:8080/callback";
+    let session = LoginSession::new(&client_id, redirect_uri);
+
+    println!("\n Starting FYERS login with a local redirect server \n");
+    let access_token = login_session_with_local_server(&session, &secret_key, DEFAULT_LOGIN_TIMEOUT).await?;
+
+    println!("\n Access token generated. Fetching user profile...");
+    let client = User::new(client_id, access_token);
+    let profile = client.get_profile().await?;
+    println!("\n Successfully fetched profile for {}", profile.name);
+
+    Ok(())
+}
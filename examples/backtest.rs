@@ -0,0 +1,57 @@
+pub mod helpers;
+use fyers_rust::backtest::{Backtest, BacktestConfig};
+use fyers_rust::charges::ChargeTable;
+use fyers_rust::dataapi::DataApi;
+use fyers_rust::error::FyersError;
+use std::collections::HashMap;
+
+#[tokio::main]
+async fn main() -> Result<(), FyersError> {
+    let config = helpers::config::load_config();
+    let data = DataApi::new(config.app_id, config.access_token);
+
+    let mut backtest = Backtest::new(BacktestConfig {
+        initial_capital: 200_000.0,
+        slippage: 0.0005,
+        charges: ChargeTable::default(),
+        close_at_end: true,
+    });
+
+    let symbols = ["NSE:SBIN-EQ", "NSE:RELIANCE-EQ"];
+    for symbol in symbols {
+        let history = data
+            .get_historical_data(symbol, "15", "1", "2025-07-01", "2025-08-29", "0", "0")
+            .await?;
+        backtest.add_candles(symbol, &history.candles);
+    }
+
+    // Moving average crossover: long while the 10 bar average is above the 30 bar average
+    let mut closes: HashMap<String, Vec<f64>> = HashMap::new();
+    let report = backtest.run(|ctx, symbol, candle| {
+        let history = closes.entry(symbol.to_string()).or_default();
+        history.push(candle.close());
+        if history.len() < 30 {
+            return;
+        }
+        let average = |n: usize| history[history.len() - n..].iter().sum::<f64>() / n as f64;
+        let (fast, slow) = (average(10), average(30));
+
+        let position = ctx.position(symbol);
+        if fast > slow && position == 0 && !ctx.has_pending(symbol) {
+            let qty = (ctx.cash() * 0.4 / candle.close()) as i64;
+            ctx.buy(symbol, qty);
+        } else if fast < slow && position > 0 {
+            ctx.close(symbol);
+        }
+    });
+
+    for trade in &report.trades {
+        println!(
+            "{} {} x{} {} -> {} net {:.2}",
+            trade.symbol, trade.entry_time, trade.qty, trade.entry_price, trade.exit_price, trade.net_pnl
+        );
+    }
+    println!("\n{:#?}", report.summary);
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+pub mod helpers;
+use fyers_rust::broker::{Broker, LiveBroker};
+use fyers_rust::models::SingleOrderRequest;
+use fyers_rust::paper::PaperBroker;
+use fyers_rust::error::FyersError;
+
+// Strategy code only sees the Broker trait, so it runs the same on paper and live
+async fn buy_and_report<B: Broker>(broker: &B, symbol: &str, qty: i64) -> Result<(), FyersError> {
+    let order = SingleOrderRequest {
+        symbol: symbol.to_string(),
+        qty,
+        order_type: 2,
+        side: 1,
+        product_type: "INTRADAY".to_string(),
+        validity: "DAY".to_string(),
+        offline_order: false,
+        limit_price: 0.0,
+        stop_price: 0.0,
+        disclosed_qty: 0,
+        stop_loss: 0.0,
+        take_profit: 0.0,
+        order_tag: None,
+    };
+    let placed = broker.place_order(&order).await?;
+    println!("Placed order {}", placed.id);
+
+    let positions = broker.positions().await?;
+    println!("Open positions: {}, P&L: {:.2}", positions.overall.count_open, positions.overall.pl_total);
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), FyersError> {
+    let config = helpers::config::load_config();
+
+    let paper = PaperBroker::new(100_000.0);
+    paper.on_price("NSE:SBIN-EQ", 800.0);
+    buy_and_report(&paper, "NSE:SBIN-EQ", 1).await?;
+
+    let live = LiveBroker::new(config.app_id, config.access_token);
+    buy_and_report(&live, "NSE:SBIN-EQ", 1).await?;
+
+    Ok(())
+}
@@ -0,0 +1,43 @@
+pub mod helpers;
+use fyers_rust::charges::{ChargeTable, Segment};
+use fyers_rust::transaction::Transaction;
+use fyers_rust::error::FyersError;
+
+#[tokio::main]
+async fn main() -> Result<(), FyersError> {
+    let config = helpers::config::load_config();
+    let transaction = Transaction::new(config.app_id, config.access_token);
+
+    // Rates can be overridden from a JSON file, e.g. after an STT revision:
+    // let table = ChargeTable::from_file("charges.json")?;
+    let mut table = ChargeTable::default();
+    table.rates_mut(Segment::EquityOptions).stt_sell = 0.001;
+
+    // The charges of a hypothetical intraday round trip of 100 shares at 1500
+    let buy = table.compute(Segment::EquityIntraday, 1, 150_000.0);
+    let sell = table.compute(Segment::EquityIntraday, -1, 150_000.0);
+    println!("Intraday round trip charges: {:#?}", buy + sell);
+
+    let trades = transaction.get_trades(None).await?;
+    let mut total = 0.0;
+    for trade in &trades.trade_book {
+        let charges = table.for_trade(trade);
+        total += charges.total();
+        println!(
+            "{} {:?} x{} @ {} -> brokerage {:.2}, STT/CTT {:.2}, exchange {:.2}, SEBI {:.2}, stamp {:.2}, GST {:.2}",
+            trade.symbol,
+            Segment::of_trade(trade),
+            trade.traded_qty,
+            trade.trade_price,
+            charges.brokerage,
+            charges.stt,
+            charges.exchange,
+            charges.sebi,
+            charges.stamp_duty,
+            charges.gst
+        );
+    }
+    println!("\n Total charges of the day: {:.2}", total);
+
+    Ok(())
+}
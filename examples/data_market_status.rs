@@ -0,0 +1,28 @@
+pub mod helpers;
+use fyers_rust::dataapi::DataApi;
+use fyers_rust::error::FyersError;
+
+#[tokio::main]
+async fn main() -> Result<(), FyersError> {
+    let config = helpers::config::load_config();
+    let data = DataApi::new(config.app_id, config.access_token);
+
+    let response = data.get_market_status().await?;
+    for status in &response.market_status {
+        println!(
+            "exchange {} segment {} {:?}: {:?}",
+            status.exchange, status.segment, status.market_type, status.status
+        );
+    }
+
+    // NSE capital market
+    if response.is_open(10, 10) {
+        println!("\n NSE equities are open");
+    } else if response.accepts_orders(10, 10) {
+        println!("\n NSE equities are taking orders before or after the session");
+    } else {
+        println!("\n NSE equities are not open");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,26 @@
+pub mod helpers;
+use fyers_rust::dataapi::DataApi;
+use fyers_rust::error::FyersError;
+
+#[tokio::main]
+async fn main() -> Result<(), FyersError> {
+    let config = helpers::config::load_config();
+    let data = DataApi::new(config.app_id, config.access_token);
+
+    let symbols = [
+        "NSE:SBIN-EQ", "NSE:RELIANCE-EQ", "NSE:TCS-EQ", "NSE:INFY-EQ", "NSE:HDFCBANK-EQ",
+        "NSE:ICICIBANK-EQ", "NSE:ITC-EQ", "NSE:LT-EQ", "NSE:AXISBANK-EQ", "NSE:NIFTY50-INDEX",
+    ];
+
+    println!("\n Fetching quotes for {} symbols ... ", symbols.len());
+    let quotes = data.get_market_quotes_batched(&symbols).await;
+
+    for symbol in symbols {
+        match &quotes[symbol] {
+            Ok(quote) => println!("{:<20} {:>10.2} ({:+.2}%)", symbol, quote.lp, quote.chp),
+            Err(e) => eprintln!("{:<20} error: {}", symbol, e),
+        }
+    }
+
+    Ok(())
+}
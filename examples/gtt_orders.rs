@@ -0,0 +1,37 @@
+pub mod helpers;
+use fyers_rust::models::{GttLeg, GttOrderRequest};
+use fyers_rust::orders::Order;
+use fyers_rust::error::FyersError;
+
+#[tokio::main]
+async fn main() -> Result<(), FyersError> {
+    let config = helpers::config::load_config();
+    let orderclass = Order::new(config.app_id, config.access_token);
+
+    // Target at 900 and stop loss at 700 for a long delivery position of 10 shares
+    let request = GttOrderRequest::oco(
+        "NSE:SBIN-EQ",
+        -1,
+        "CNC",
+        GttLeg::new(900.0, 900.0, 10),
+        GttLeg::new(700.0, 700.0, 10),
+    );
+
+    let placed = orderclass.place_gtt_order(&request).await?;
+    println!("\n Placed GTT order {}", placed.id);
+
+    let mut order_info = request.order_info.clone();
+    order_info.leg1 = GttLeg::new(950.0, 950.0, 10);
+    orderclass.modify_gtt_order(&placed.id, order_info).await?;
+    println!("\n Modified GTT order {}", placed.id);
+
+    let gtt_orders = orderclass.get_gtt_orders().await?;
+    for order in &gtt_orders.order_book {
+        println!("{} {} qty:{} trigger:{} status:{}", order.id, order.symbol, order.qty, order.price_trigger, order.ord_status);
+    }
+
+    orderclass.cancel_gtt_order(&placed.id).await?;
+    println!("\n Cancelled GTT order {}", placed.id);
+
+    Ok(())
+}
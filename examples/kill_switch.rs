@@ -0,0 +1,43 @@
+pub mod helpers;
+use fyers_rust::broker::LiveBroker;
+use fyers_rust::kill_switch::KillSwitch;
+use fyers_rust::risk::{RiskLimits, RiskManager};
+use fyers_rust::error::FyersError;
+use std::sync::Arc;
+
+#[tokio::main]
+async fn main() -> Result<(), FyersError> {
+    let config = helpers::config::load_config();
+    let kill_switch = Arc::new(KillSwitch::new(LiveBroker::new(config.app_id, config.access_token)));
+
+    // Ctrl-C, SIGTERM or a breach of the daily loss limit stops everything
+    let handler = kill_switch.clone().spawn_handler();
+    let limits = RiskLimits {
+        max_daily_loss: Some(5_000.0),
+        ..Default::default()
+    };
+    let _broker = RiskManager::new(kill_switch.clone(), limits).with_kill_switch(kill_switch.handle());
+
+    // ... run the strategy with `_broker` here ...
+
+    println!("Press Ctrl-C to cancel all orders and square off all positions");
+    let report = handler
+        .await
+        .map_err(|e| FyersError::Unknown(e.to_string()))?;
+
+    println!("\n Cancelled: {:?}", report.cancelled);
+    for (symbol, product_type, qty, id) in &report.exited {
+        println!(" Exited {} {} x{} with order {}", symbol, product_type, qty, id);
+    }
+    for (id, e) in &report.cancel_failed {
+        println!(" Could not cancel {}: {}", id, e);
+    }
+    for (symbol, _, e) in &report.exit_failed {
+        println!(" Could not exit {}: {}", symbol, e);
+    }
+    for e in &report.errors {
+        println!(" Error: {}", e);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+pub mod helpers;
+use fyers_rust::models::SubscriptionMode;
+use fyers_rust::pnl::PnlEngine;
+use fyers_rust::transaction::Transaction;
+use fyers_rust::websocket::MarketDataSocket;
+use fyers_rust::error::FyersError;
+use std::time::Duration;
+
+#[tokio::main]
+async fn main() -> Result<(), FyersError> {
+    let config = helpers::config::load_config();
+    let transaction = Transaction::new(config.app_id.clone(), config.access_token.clone());
+
+    let mut engine = PnlEngine::new().with_mtm_interval(Duration::from_secs(60));
+    engine.seed_from(&transaction).await?;
+
+    let symbols: Vec<String> = engine.positions().map(|p| p.symbol.clone()).collect();
+    let symbols: Vec<&str> = symbols.iter().map(String::as_str).collect();
+    if symbols.is_empty() {
+        println!("No positions today");
+        return Ok(());
+    }
+
+    let mut client = MarketDataSocket::new(config.app_id, config.access_token);
+    client.connect().await?;
+    client.subscribe(&symbols, SubscriptionMode::Quote).await?;
+
+    client.listen(|socket_message| {
+        engine.on_message(&socket_message);
+        let total = engine.total();
+        println!(
+            "Realized: {:.2} Unrealized: {:.2} Total: {:.2}",
+            total.realized, total.unrealized, total.total
+        );
+        for (symbol, pnl) in engine.by_symbol() {
+            println!("  {} {:.2}", symbol, pnl.total);
+        }
+    }).await?;
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+pub mod helpers;
+use fyers_rust::models::SingleOrderRequest;
+use fyers_rust::orders::Order;
+use fyers_rust::user::User;
+use fyers_rust::error::FyersError;
+
+fn market_order(symbol: &str, qty: i64, side: i64) -> SingleOrderRequest {
+    SingleOrderRequest {
+        symbol: symbol.to_string(),
+        qty,
+        order_type: 2,
+        side,
+        product_type: "MARGIN".to_string(),
+        validity: "DAY".to_string(),
+        offline_order: false,
+        limit_price: 0.0,
+        stop_price: 0.0,
+        disclosed_qty: 0,
+        stop_loss: 0.0,
+        take_profit: 0.0,
+        order_tag: None,
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), FyersError> {
+    let config = helpers::config::load_config();
+    let orderclass = Order::new(config.app_id.clone(), config.access_token.clone());
+    let user = User::new(config.app_id, config.access_token);
+
+    // A short straddle hedged with long wings
+    let basket = vec![
+        market_order("NSE:NIFTY25SEP25000CE", 75, -1),
+        market_order("NSE:NIFTY25SEP25000PE", 75, -1),
+        market_order("NSE:NIFTY25SEP25500CE", 75, 1),
+        market_order("NSE:NIFTY25SEP24500PE", 75, 1),
+    ];
+
+    let margin = orderclass.basket_margin(&basket).await?;
+    for (order, leg_margin) in basket.iter().zip(&margin.leg_margins) {
+        println!("{} {}: {:.2}", order.symbol, order.side, leg_margin);
+    }
+    println!(
+        "\n Basket margin {:.2}, standalone {:.2}, hedge benefit {:.2}",
+        margin.total_margin, margin.standalone_margin, margin.margin_benefit
+    );
+    println!("Available margin {:.2}, shortfall {:.2}", margin.available_margin, margin.shortfall());
+
+    let funds = user.get_funds().await?.funds();
+    println!("Available balance from /funds: {:.2}", funds.equity.available_balance);
+    if !margin.is_sufficient() {
+        println!("Not enough margin to place the basket");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,29 @@
+pub mod helpers;
+use fyers_rust::models::{MultiLegOrderRequest, OrderLeg};
+use fyers_rust::orders::Order;
+use fyers_rust::error::FyersError;
+
+#[tokio::main]
+async fn main() -> Result<(), FyersError> {
+    let config = helpers::config::load_config();
+    let orderclass = Order::new(config.app_id, config.access_token);
+
+    // Short straddle: sell the call and the put at the same strike
+    let request = MultiLegOrderRequest::two_leg(
+        "MARGIN",
+        OrderLeg::limit("NSE:NIFTY25SEP25000CE", 75, -1, 120.0),
+        OrderLeg::limit("NSE:NIFTY25SEP25000PE", 75, -1, 110.0),
+    )
+    .with_order_tag("straddle");
+
+    let result = orderclass.multi_leg_order(&request).await?;
+    println!("\n Placed multi-leg order {}", result.response.id);
+    for leg in &result.legs {
+        println!(
+            "{} {} status:{:?} filled:{} @ {} {}",
+            leg.leg, leg.symbol, leg.status, leg.filled_qty, leg.traded_price, leg.message
+        );
+    }
+
+    Ok(())
+}
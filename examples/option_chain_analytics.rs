@@ -0,0 +1,47 @@
+pub mod helpers;
+use fyers_rust::dataapi::DataApi;
+use fyers_rust::error::FyersError;
+use fyers_rust::options::{enrich_chain, ChainAnalytics, PricingModel};
+
+#[tokio::main]
+async fn main() -> Result<(), FyersError> {
+    let config = helpers::config::load_config();
+    let data = DataApi::new(config.app_id, config.access_token);
+
+    println!("\n Fetching option chain data ... ");
+    let response = data
+        .get_option_chain("NSE:NIFTY50-INDEX", Some("10"), None)
+        .await?;
+
+    let chain = response
+        .data
+        .as_ref()
+        .ok_or_else(|| FyersError::Unknown("Option chain response had no data".to_string()))?;
+    let analytics = ChainAnalytics::new(chain);
+
+    println!("ATM strike:   {:?}", analytics.atm_strike());
+    println!("Max pain:     {:?}", analytics.max_pain());
+    println!("PCR (OI):     {:?}", analytics.pcr_oi());
+    println!("PCR (volume): {:?}", analytics.pcr_volume());
+    println!("Support:      {:?}", analytics.support(3));
+    println!("Resistance:   {:?}", analytics.resistance(3));
+
+    for (option, buildup) in analytics.buildups() {
+        println!("{:<30} {:?}", option.symbol, buildup);
+    }
+
+    if let Some(expiry) = chain.expiry_data.first() {
+        println!("\n Greeks for expiry {}", expiry.date);
+        for option in enrich_chain(&response, expiry, 0.065, PricingModel::Black76)? {
+            match (option.iv, option.greeks) {
+                (Some(iv), Some(g)) => println!(
+                    "{:<30} iv:{:>6.2}% delta:{:>6.3} gamma:{:>8.5} theta:{:>7.2} vega:{:>6.2}",
+                    option.symbol, iv * 100.0, g.delta, g.gamma, g.theta, g.vega
+                ),
+                _ => println!("{:<30} iv: n/a", option.symbol),
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+pub mod helpers;
+use fyers_rust::models::SingleOrderRequest;
+use fyers_rust::order_tracker::OrderTracker;
+use fyers_rust::orders::Order;
+use fyers_rust::transaction::Transaction;
+use fyers_rust::error::FyersError;
+use std::time::Duration;
+
+#[tokio::main]
+async fn main() -> Result<(), FyersError> {
+    let config = helpers::config::load_config();
+    let orderclass = Order::new(config.app_id.clone(), config.access_token.clone());
+    let transaction = Transaction::new(config.app_id, config.access_token);
+
+    let order = SingleOrderRequest {
+        symbol: "NSE:SBIN-EQ".to_string(),
+        qty: 1,
+        order_type: 1,
+        side: 1,
+        product_type: "INTRADAY".to_string(),
+        validity: "DAY".to_string(),
+        offline_order: false,
+        limit_price: 700.0,
+        stop_price: 0.0,
+        disclosed_qty: 0,
+        stop_loss: 0.0,
+        take_profit: 0.0,
+        order_tag: Some("tracked".to_string()),
+    };
+    let placed = orderclass.single_order(&order).await?;
+
+    let mut tracker = OrderTracker::new();
+    tracker.track(&placed.id);
+    tracker
+        .poll_until_terminal(&transaction, Duration::from_secs(2), |order, change| {
+            println!("{}: {:?} -> {:?} (filled {}) {}", order.id, change.from, change.to, change.filled_qty, change.message);
+        })
+        .await?;
+
+    if let Some(order) = tracker.get(&placed.id) {
+        println!("\n Final state: {:?}", order.state);
+        println!(" Average price: {} over {} fills", order.average_price(), order.fills.len());
+        for change in &order.history {
+            println!(" {} {:?}", change.at, change.to);
+        }
+    }
+
+    Ok(())
+}
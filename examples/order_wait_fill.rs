@@ -0,0 +1,41 @@
+pub mod helpers;
+use fyers_rust::models::SingleOrderRequest;
+use fyers_rust::orders::Order;
+use fyers_rust::transaction::{Transaction, DEFAULT_POLL_INTERVAL};
+use fyers_rust::error::FyersError;
+use std::time::Duration;
+
+#[tokio::main]
+async fn main() -> Result<(), FyersError> {
+    let config = helpers::config::load_config();
+    let orderclass = Order::new(config.app_id.clone(), config.access_token.clone());
+    let transaction = Transaction::new(config.app_id, config.access_token);
+
+    let order = SingleOrderRequest {
+        symbol: "NSE:SBIN-EQ".to_string(),
+        qty: 10,
+        order_type: 2,
+        side: 1,
+        product_type: "INTRADAY".to_string(),
+        validity: "DAY".to_string(),
+        offline_order: false,
+        limit_price: 0.0,
+        stop_price: 0.0,
+        disclosed_qty: 0,
+        stop_loss: 0.0,
+        take_profit: 0.0,
+        order_tag: None,
+    };
+    let placed = orderclass.single_order(&order).await?;
+
+    let filled = transaction
+        .wait_for_terminal_with(&placed.id, Duration::from_secs(60), DEFAULT_POLL_INTERVAL, |order| {
+            println!("Partially filled: {}/{} @ {}", order.filled_qty, order.qty, order.traded_price);
+        })
+        .await?;
+
+    println!("\n Order {} finished with status {}", filled.id, filled.status);
+    println!(" Filled {}/{} @ {}", filled.filled_qty, filled.qty, filled.traded_price);
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+pub mod helpers;
+use fyers_rust::dataapi::DataApi;
+use fyers_rust::models::SingleOrderRequest;
+use fyers_rust::paper::PaperBroker;
+use fyers_rust::error::FyersError;
+use std::time::Duration;
+
+#[tokio::main]
+async fn main() -> Result<(), FyersError> {
+    let config = helpers::config::load_config();
+    let data = DataApi::new(config.app_id, config.access_token);
+
+    let broker = PaperBroker::new(100_000.0).with_slippage(0.0005);
+
+    let order = SingleOrderRequest {
+        symbol: "NSE:SBIN-EQ".to_string(),
+        qty: 10,
+        order_type: 2,
+        side: 1,
+        product_type: "INTRADAY".to_string(),
+        validity: "DAY".to_string(),
+        offline_order: false,
+        limit_price: 0.0,
+        stop_price: 0.0,
+        disclosed_qty: 0,
+        stop_loss: 0.0,
+        take_profit: 0.0,
+        order_tag: Some("paper".to_string()),
+    };
+    let placed = broker.single_order(&order)?;
+    println!("\n Placed paper order {}", placed.id);
+
+    // Fill against live quotes, then mark the position a few times
+    for _ in 0..3 {
+        for trade in broker.update_from_quotes(&data).await {
+            println!("Filled {} {} @ {}", trade.traded_qty, trade.symbol, trade.trade_price);
+        }
+        let positions = broker.get_positions();
+        println!("P&L: {:.2}", positions.overall.pl_total);
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    }
+
+    println!("{:#?}", broker.get_funds());
+    Ok(())
+}
@@ -0,0 +1,49 @@
+pub mod helpers;
+use fyers_rust::broker::{Broker, LiveBroker};
+use fyers_rust::dataapi::DataApi;
+use fyers_rust::models::SingleOrderRequest;
+use fyers_rust::risk::{RiskLimits, RiskManager};
+use fyers_rust::error::FyersError;
+
+#[tokio::main]
+async fn main() -> Result<(), FyersError> {
+    let config = helpers::config::load_config();
+    let live = LiveBroker::new(config.app_id.clone(), config.access_token.clone());
+
+    let limits = RiskLimits {
+        max_order_qty: Some(100),
+        max_order_notional: Some(50_000.0),
+        max_open_positions: Some(5),
+        max_symbol_exposure: Some(100_000.0),
+        max_daily_loss: Some(5_000.0),
+        allowed_products: Some(vec!["INTRADAY".to_string()]),
+        allowed_segments: Some(vec![10]),
+        price_band: Some(0.05),
+        ..Default::default()
+    };
+    let broker = RiskManager::new(live, limits).with_data_api(DataApi::new(config.app_id, config.access_token));
+
+    // A fat-fingered order, stopped before it reaches the exchange
+    let order = SingleOrderRequest {
+        symbol: "NSE:SBIN-EQ".to_string(),
+        qty: 10_000,
+        order_type: 1,
+        side: 1,
+        product_type: "INTRADAY".to_string(),
+        validity: "DAY".to_string(),
+        offline_order: false,
+        limit_price: 800.0,
+        stop_price: 0.0,
+        disclosed_qty: 0,
+        stop_loss: 0.0,
+        take_profit: 0.0,
+        order_tag: None,
+    };
+    match broker.place_order(&order).await {
+        Err(FyersError::RiskRejected(violation)) => println!("\n Rejected: {}", violation),
+        Err(e) => return Err(e),
+        Ok(response) => println!("\n Placed order {}", response.id),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+pub mod helpers;
+use fyers_rust::charges::ChargeTable;
+use fyers_rust::round_trips::match_round_trips;
+use fyers_rust::transaction::Transaction;
+use fyers_rust::error::FyersError;
+
+#[tokio::main]
+async fn main() -> Result<(), FyersError> {
+    let config = helpers::config::load_config();
+    let transaction = Transaction::new(config.app_id, config.access_token);
+
+    let trades = transaction.get_trades(None).await?;
+    let positions = transaction.get_positions().await?;
+
+    let report = match_round_trips(&trades.trade_book, Some(&positions), &ChargeTable::default());
+    for trip in &report.round_trips {
+        println!(
+            "{} {} {} x{} {} -> {} held {:?} gross {:.2} charges {:.2} net {:.2}{}",
+            trip.symbol,
+            trip.product_type,
+            if trip.side == 1 { "LONG" } else { "SHORT" },
+            trip.qty,
+            trip.entry_price,
+            trip.exit_price,
+            trip.holding_period(),
+            trip.gross_pnl,
+            trip.charges,
+            trip.net_pnl,
+            if trip.carried_forward { " (carried forward)" } else { "" }
+        );
+    }
+    for lot in &report.open_lots {
+        println!("Open: {} {} x{} @ {}", lot.symbol, lot.side, lot.qty, lot.price);
+    }
+    println!("\n Gross {:.2}, charges {:.2}, net {:.2}", report.gross_pnl(), report.charges(), report.net_pnl());
+
+    Ok(())
+}
@@ -0,0 +1,31 @@
+pub mod helpers;
+use fyers_rust::error::FyersError;
+use fyers_rust::token_store::{FileTokenStore, StoredToken, TokenStore};
+use fyers_rust::user::User;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[tokio::main]
+async fn main() -> Result<(), FyersError> {
+    let config = helpers::config::load_config();
+    let store = Arc::new(FileTokenStore::new("fyers_token.json"));
+
+    // Seed the store from .env the first time, afterwards the stored token is used
+    if store.load()?.is_none() {
+        println!("\n Saving access token from .env to {}", store.path().display());
+        store.save(&StoredToken::from_access_token(&config.access_token)?)?;
+    }
+
+    if let Some(token) = store.load()? {
+        println!("\n Token for {:?} expires in {:?}", token.fy_id, token.time_to_expiry());
+        if token.expires_within(Duration::from_secs(60 * 60)) {
+            println!(" Token expires within the hour, log in again soon.");
+        }
+    }
+
+    let client = User::from_token_store(config.app_id, store)?;
+    let profile = client.get_profile().await?;
+    println!("\n Successfully fetched profile for {}", profile.name);
+
+    Ok(())
+}
@@ -26,6 +26,18 @@ async fn main() -> Result<(), FyersError> {
     println!("\n Data saved to {}", &filename);
     println!("{:#?}", response);
 
+    let funds = response.funds();
+    println!(
+        "\n Equity: available {:.2}, utilized {:.2}, realized P&L {:.2}, collaterals {:.2}, payin {:.2}, start of day {:.2}",
+        funds.equity.available_balance,
+        funds.equity.utilized_margin,
+        funds.equity.realized_pl,
+        funds.equity.collaterals,
+        funds.equity.payin,
+        funds.equity.limit_at_start_of_day
+    );
+    println!(" Commodity: available {:.2}, utilized {:.2}", funds.commodity.available_balance, funds.commodity.utilized_margin);
+
     if response.s == "ok" {
         Ok(())
     } else {
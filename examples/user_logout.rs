@@ -0,0 +1,25 @@
+use fyers_rust::user::User;
+use fyers_rust::error::FyersError;
+use std::env;
+
+#[tokio::main]
+async fn main() -> Result<(), FyersError> {
+    dotenvy::dotenv().expect(".env file not found");
+
+    let app_id = env::var("FYERS_APP_ID").expect("FYERS_APP_ID must be set in .env");
+    let access_token = env::var("FYERS_ACCESS_TOKEN").expect("FYERS_ACCESS_TOKEN must be set in .env");
+
+    let client = User::new(app_id, access_token);
+
+    println!("\n Logging out...");
+    client.logout().await?;
+    println!("\n Access token revoked.");
+
+    // Any further call on the client fails without reaching the API
+    match client.get_profile().await {
+        Ok(_) => println!("\n Unexpectedly fetched profile after logout"),
+        Err(e) => println!("\n Profile fetch after logout failed as expected: {}", e),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,39 @@
+pub mod helpers;
+use fyers_rust::models::SubscriptionMode;
+use fyers_rust::websocket::{CandleBuilder, CandleEvent, MarketDataSocket, Resolution};
+use fyers_rust::error::FyersError;
+
+#[tokio::main]
+async fn main() -> Result<(), FyersError> {
+    let config = helpers::config::load_config();
+
+    let mut client = MarketDataSocket::new(config.app_id, config.access_token);
+    client.connect().await?;
+
+    let symbols = &["NSE:SBIN-EQ", "NSE:RELIANCE-EQ"];
+    client.subscribe(symbols, SubscriptionMode::Quote).await?;
+    println!("Successfully subscribed to symbols: {:?}\n", symbols);
+
+    let resolutions: Vec<Resolution> = [1, 5].into_iter().filter_map(Resolution::minutes).collect();
+    let mut builder = CandleBuilder::new(&resolutions);
+
+    client.listen(|socket_message| {
+        for event in builder.on_message(&socket_message) {
+            if let CandleEvent::Closed { symbol, resolution, candle } = event {
+                println!(
+                    "{} [{}s] {} O:{} H:{} L:{} C:{} V:{}",
+                    symbol,
+                    resolution.as_secs(),
+                    candle.timestamp(),
+                    candle.open(),
+                    candle.high(),
+                    candle.low(),
+                    candle.close(),
+                    candle.volume()
+                );
+            }
+        }
+    }).await?;
+
+    Ok(())
+}
@@ -0,0 +1,268 @@
+use crate::credentials::Credentials;
+use crate::dataapi::DataApi;
+use crate::error::FyersError;
+use crate::models::{Funds, FundsResponse, HoldingsResponse, PositionsResponse};
+use crate::orders::Order;
+use crate::rate_limiter::RateLimiter;
+use crate::token_store::TokenStore;
+use crate::transaction::Transaction;
+use crate::user::User;
+use futures_util::future::join_all;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Arc;
+
+/// The clients of a single FYERS account. They share their credentials, so after
+/// [User::logout] every client of the account fails with an auth error.
+#[derive(Debug, Clone)]
+pub struct Account {
+    pub fy_id: String,
+    pub user: User,
+    pub order: Order,
+    pub transaction: Transaction,
+    pub data: DataApi,
+}
+
+impl Account {
+    /// # Description
+    /// Create the clients for an account from an access token
+    ///
+    /// # Arguments
+    /// * `fy_id` - The FYERS client id of the account (e.g. "XY12345")
+    /// * `app_id` - The app id the token was issued for
+    /// * `access_token` - The access token of the account
+    pub fn new(fy_id: &str, app_id: String, access_token: String) -> Self {
+        Self::with_credentials(fy_id, Credentials::new(app_id, access_token))
+    }
+
+    /// # Description
+    /// Create the clients for an account that read their access token from a token store
+    ///
+    /// # Arguments
+    /// * `fy_id` - The FYERS client id of the account (e.g. "XY12345")
+    /// * `app_id` - The app id the token was issued for
+    /// * `token_store` - The token store holding the access token of the account
+    pub fn from_token_store(fy_id: &str, app_id: String, token_store: Arc<dyn TokenStore>) -> Result<Self, FyersError> {
+        Ok(Self::with_credentials(fy_id, Credentials::from_token_store(app_id, token_store)?))
+    }
+
+    // The clients share one set of credentials, so logging out through `user` stops them all, and
+    // one rate limiter, so the calls fanning out into several requests are limited together
+    fn with_credentials(fy_id: &str, credentials: Credentials) -> Self {
+        let rate_limiter = Arc::new(RateLimiter::default());
+        Self {
+            fy_id: fy_id.to_string(),
+            user: User::with_credentials(credentials.clone()),
+            order: Order::with_credentials(credentials.clone()).with_rate_limiter(rate_limiter.clone()),
+            transaction: Transaction::with_credentials(credentials.clone()),
+            data: DataApi::with_credentials(credentials).with_rate_limiter(rate_limiter),
+        }
+    }
+}
+
+/// The results of a call made on every account, keyed by `fy_id`. A failure on one account does
+/// not hide the results of the others.
+#[derive(Debug)]
+pub struct AccountResults<T> {
+    pub by_account: BTreeMap<String, Result<T, FyersError>>,
+}
+
+impl<T> AccountResults<T> {
+    /// The accounts the call succeeded for
+    pub fn successes(&self) -> impl Iterator<Item = (&str, &T)> {
+        self.by_account
+            .iter()
+            .filter_map(|(fy_id, result)| result.as_ref().ok().map(|value| (fy_id.as_str(), value)))
+    }
+
+    /// The accounts the call failed for
+    pub fn errors(&self) -> impl Iterator<Item = (&str, &FyersError)> {
+        self.by_account
+            .iter()
+            .filter_map(|(fy_id, result)| result.as_ref().err().map(|e| (fy_id.as_str(), e)))
+    }
+
+    pub fn get(&self, fy_id: &str) -> Option<&Result<T, FyersError>> {
+        self.by_account.get(fy_id)
+    }
+}
+
+impl AccountResults<PositionsResponse> {
+    /// Total P&L across the accounts that returned their positions
+    pub fn pl_total(&self) -> f64 {
+        self.successes().map(|(_, p)| p.overall.pl_total).sum()
+    }
+    pub fn pl_realized(&self) -> f64 {
+        self.successes().map(|(_, p)| p.overall.pl_realized).sum()
+    }
+    pub fn pl_unrealized(&self) -> f64 {
+        self.successes().map(|(_, p)| p.overall.pl_unrealized).sum()
+    }
+    pub fn count_open(&self) -> i64 {
+        self.successes().map(|(_, p)| p.overall.count_open).sum()
+    }
+}
+
+impl AccountResults<HoldingsResponse> {
+    /// Total investment across the accounts that returned their holdings
+    pub fn total_investment(&self) -> f64 {
+        self.successes().map(|(_, h)| h.overall.total_investment).sum()
+    }
+    pub fn total_current_value(&self) -> f64 {
+        self.successes().map(|(_, h)| h.overall.total_current_value).sum()
+    }
+    pub fn total_pl(&self) -> f64 {
+        self.successes().map(|(_, h)| h.overall.total_pl).sum()
+    }
+}
+
+impl AccountResults<FundsResponse> {
+    /// # Description
+    /// The funds of the accounts that returned them, added up per segment
+    pub fn total_funds(&self) -> Funds {
+        self.successes().map(|(_, f)| f.funds()).fold(Funds::default(), |total, funds| total + funds)
+    }
+}
+
+/// A registry of FYERS accounts keyed by `fy_id`, for running read calls across all of them
+#[derive(Debug, Clone, Default)]
+pub struct AccountRegistry {
+    accounts: BTreeMap<String, Account>,
+}
+
+impl AccountRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Description
+    /// Add an account, replacing any account with the same `fy_id`
+    pub fn add(&mut self, account: Account) -> Option<Account> {
+        self.accounts.insert(account.fy_id.clone(), account)
+    }
+
+    /// # Description
+    /// Add an account from an access token
+    ///
+    /// # Arguments
+    /// * `fy_id` - The FYERS client id of the account
+    /// * `app_id` - The app id the token was issued for
+    /// * `access_token` - The access token of the account
+    pub fn add_with_token(&mut self, fy_id: &str, app_id: String, access_token: String) {
+        self.add(Account::new(fy_id, app_id, access_token));
+    }
+
+    /// # Description
+    /// Add an account that reads its access token from a token store
+    ///
+    /// # Arguments
+    /// * `fy_id` - The FYERS client id of the account
+    /// * `app_id` - The app id the token was issued for
+    /// * `token_store` - The token store holding the access token of the account
+    pub fn add_with_token_store(&mut self, fy_id: &str, app_id: String, token_store: Arc<dyn TokenStore>) -> Result<(), FyersError> {
+        self.add(Account::from_token_store(fy_id, app_id, token_store)?);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, fy_id: &str) -> Option<Account> {
+        self.accounts.remove(fy_id)
+    }
+
+    pub fn get(&self, fy_id: &str) -> Option<&Account> {
+        self.accounts.get(fy_id)
+    }
+
+    pub fn fy_ids(&self) -> impl Iterator<Item = &str> {
+        self.accounts.keys().map(String::as_str)
+    }
+
+    pub fn accounts(&self) -> impl Iterator<Item = &Account> {
+        self.accounts.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// # Description
+    /// Run a call on every account concurrently and collect the results by `fy_id`
+    ///
+    /// # Arguments
+    /// * `call` - The call to make, given each account
+    pub async fn fan_out<'a, T, F, Fut>(&'a self, call: F) -> AccountResults<T>
+    where
+        F: Fn(&'a Account) -> Fut,
+        Fut: Future<Output = Result<T, FyersError>> + 'a,
+    {
+        let calls = self.accounts.values().map(|account| {
+            let call = call(account);
+            async move { (account.fy_id.clone(), call.await) }
+        });
+        AccountResults {
+            by_account: join_all(calls).await.into_iter().collect(),
+        }
+    }
+
+    /// # Description
+    /// Fetch the positions of every account
+    pub async fn positions(&self) -> AccountResults<PositionsResponse> {
+        self.fan_out(|account| account.transaction.get_positions()).await
+    }
+
+    /// # Description
+    /// Fetch the funds of every account
+    pub async fn funds(&self) -> AccountResults<FundsResponse> {
+        self.fan_out(|account| account.user.get_funds()).await
+    }
+
+    /// # Description
+    /// Fetch the holdings of every account
+    pub async fn holdings(&self) -> AccountResults<HoldingsResponse> {
+        self.fan_out(|account| account.user.get_holdings()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::user::FundLimit;
+
+    fn funds(available: f64, utilized: f64, commodity_available: f64) -> FundsResponse {
+        let limit = |id: i64, title: &str, equity_amount: f64, commodity_amount: f64| FundLimit {
+            id,
+            title: title.to_string(),
+            equity_amount,
+            commodity_amount,
+        };
+        FundsResponse {
+            s: "ok".to_string(),
+            code: 200,
+            message: String::new(),
+            fund_limit: vec![
+                limit(2, "Utilized Amount", utilized, 0.0),
+                limit(10, "Available Balance", available, commodity_available),
+            ],
+        }
+    }
+
+    #[test]
+    fn totals_funds_over_the_successful_accounts() {
+        let results = AccountResults {
+            by_account: BTreeMap::from([
+                ("XA00001".to_string(), Ok(funds(10_000.0, 2_500.0, 1_000.0))),
+                ("XB00002".to_string(), Ok(funds(5_000.0, 500.0, 0.0))),
+                ("XC00003".to_string(), Err(FyersError::AuthError("expired".to_string()))),
+            ]),
+        };
+
+        let total = results.total_funds();
+        assert_eq!(total.equity.available_balance, 15_000.0);
+        assert_eq!(total.equity.utilized_margin, 3_000.0);
+        assert_eq!(total.commodity.available_balance, 1_000.0);
+        assert_eq!(total.total_available(), 16_000.0);
+    }
+}
@@ -1,7 +1,12 @@
 use crate::error::FyersError;
+use crate::token_store::{StoredToken, TokenStore};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use rand::RngCore;
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
 
 const FYERS_API_BASE_URL: &str = "https://api-t1.fyers.in/api/v3";
 
@@ -11,7 +16,9 @@ struct TokenResponse {
     s: String,
     code: i32,
     message: String,
-    access_token: Option<String>
+    access_token: Option<String>,
+    #[serde(default)]
+    refresh_token: Option<String>,
 }
 
 /// The TokenRequest struct
@@ -23,6 +30,16 @@ struct TokenRequest<'a> {
     code: &'a str,
 }
 
+/// The RefreshTokenRequest struct
+#[derive(Serialize, Debug)]
+struct RefreshTokenRequest<'a> {
+    grant_type: &'a str,
+    #[serde(rename = "appIdHash")]
+    app_id_hash: &'a str,
+    refresh_token: &'a str,
+    pin: &'a str,
+}
+
 /// # Description
 /// Generate the initial authentication URL where user must log in. [Read more](https://myapi.fyers.in/docsv3#tag/Authentication-and-Login-Flow-User-Apps/paths/~1Authentication%20&%20Login%20Flow%20-%20User%20Apps/patch)
 ///
@@ -31,10 +48,126 @@ struct TokenRequest<'a> {
 /// * `redirect_uri` - Redirect URI
 /// * `state` - A unique, random string to prevent CSRF attacks
 pub fn generate_auth_url(client_id: &str, redirect_uri: &str, state: &str) -> String {
-    format!(
-        "{}/generate-authcode?client_id={}&redirect_uri={}&response_type=code&state={}",
-        FYERS_API_BASE_URL, client_id, redirect_uri, state
+    let base = format!("{}/generate-authcode", FYERS_API_BASE_URL);
+    Url::parse_with_params(
+        &base,
+        &[
+            ("client_id", client_id),
+            ("redirect_uri", redirect_uri),
+            ("response_type", "code"),
+            ("state", state),
+        ],
     )
+    .expect("FYERS_API_BASE_URL is a valid URL")
+    .to_string()
+}
+
+/// A single login attempt. Generates a random `state` for the auth URL and checks it when the
+/// user comes back through the redirect URI.
+#[derive(Debug, Clone)]
+pub struct LoginSession {
+    client_id: String,
+    redirect_uri: String,
+    state: String,
+}
+
+impl LoginSession {
+    /// # Description
+    /// Start a new login session with a cryptographically random `state`
+    ///
+    /// # Arguments
+    /// * `client_id` - FYERS client id
+    /// * `redirect_uri` - Redirect URI registered for the app
+    pub fn new(client_id: &str, redirect_uri: &str) -> Self {
+        let mut bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let state = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        Self {
+            client_id: client_id.to_string(),
+            redirect_uri: redirect_uri.to_string(),
+            state,
+        }
+    }
+
+    pub fn client_id(&self) -> &str {
+        &self.client_id
+    }
+
+    pub fn state(&self) -> &str {
+        &self.state
+    }
+
+    pub fn redirect_uri(&self) -> &str {
+        &self.redirect_uri
+    }
+
+    /// # Description
+    /// The URL the user must open to log in
+    pub fn auth_url(&self) -> String {
+        generate_auth_url(&self.client_id, &self.redirect_uri, &self.state)
+    }
+
+    /// # Description
+    /// Validate the URL the user was redirected to and extract the `auth_code`. Fails if the
+    /// `state` does not match this session or FYERS reported an error.
+    ///
+    /// # Arguments
+    /// * `redirect_url` - The full URL from the browser's address bar after logging in
+    pub fn parse_redirect(&self, redirect_url: &str) -> Result<String, FyersError> {
+        let url = Url::parse(redirect_url.trim())
+            .map_err(|e| FyersError::AuthError(format!("Invalid redirect URL: {}", e)))?;
+        parse_redirect_url(&url, &self.state)
+    }
+
+    /// # Description
+    /// Validate the redirect URL and exchange its `auth_code` for an access token
+    ///
+    /// # Arguments
+    /// * `client_secret` - FYERS client secret
+    /// * `redirect_url` - The full URL from the browser's address bar after logging in
+    pub async fn complete(&self, client_secret: &str, redirect_url: &str) -> Result<String, FyersError> {
+        let auth_code = self.parse_redirect(redirect_url)?;
+        generate_access_token(&self.client_id, client_secret, &auth_code).await
+    }
+}
+
+/// Extract the auth code from a redirect URL, checking the state and the reported status
+pub(crate) fn parse_redirect_url(url: &Url, state: &str) -> Result<String, FyersError> {
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+
+    // FYERS reports failures (e.g. the user cancelled) with s=error and a message
+    if let Some(s) = param("s") {
+        if s != "ok" {
+            let message = param("message").unwrap_or_else(|| "Login was not completed".to_string());
+            return Err(FyersError::AuthError(format!("Login failed: {}", message)));
+        }
+    }
+
+    if !redirect_state_matches(url, state) {
+        return Err(FyersError::AuthError(
+            "The state returned by the login redirect does not match, possible CSRF attempt".to_string(),
+        ));
+    }
+
+    param("auth_code")
+        .filter(|code| !code.is_empty())
+        .ok_or_else(|| FyersError::AuthError("The login redirect did not contain an auth_code".to_string()))
+}
+
+/// Whether a redirect URL carries the expected `state`
+pub(crate) fn redirect_state_matches(url: &Url, state: &str) -> bool {
+    url.query_pairs()
+        .find(|(key, _)| key == "state")
+        .is_some_and(|(_, returned)| constant_time_eq(returned.as_bytes(), state.as_bytes()))
+}
+
+/// Compare two byte strings without exiting early on the first difference
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
 }
 
 /// # Description
@@ -49,14 +182,142 @@ pub async fn generate_access_token(
     client_secret: &str,
     auth_code: &str,
 ) -> Result<String, FyersError> {
-    // The steps for authentication are outlined in the docs here:
-    // * https://myapi.fyers.in/docsv3#tag/Authentication-and-Login-Flow-User-Apps/paths/~1Authentication%20&%20Login%20Flow%20-%20User%20Apps/patch
+    let (access_token, _) = validate_auth_code(client_id, client_secret, auth_code).await?;
+    Ok(access_token)
+}
 
-    // 1. Create SHA-256 hash of `client_id:secret_key`
+/// # Description
+/// Exchange the temporary `auth_code` for an access token and a refresh token. The refresh token
+/// can be used with [refresh_access_token] to get a new access token without logging in again.
+///
+/// # Arguments
+/// * `client_id` - FYERS client id
+/// * `client_secret` - FYERS client secret
+/// * `auth_code` - Temporary authorization code
+pub async fn generate_tokens(
+    client_id: &str,
+    client_secret: &str,
+    auth_code: &str,
+) -> Result<StoredToken, FyersError> {
+    let (access_token, refresh_token) = validate_auth_code(client_id, client_secret, auth_code).await?;
+    let mut token = StoredToken::from_access_token(&access_token)?;
+    token.refresh_token = refresh_token;
+    Ok(token)
+}
+
+/// # Description
+/// Get a new access token using a refresh token, without a browser login. [Read more](https://myapi.fyers.in/docsv3#tag/Authentication-and-Login-Flow-User-Apps/paths/~1Authentication%20&%20Login%20Flow%20-%20User%20Apps/patch)
+///
+/// # Arguments
+/// * `client_id` - FYERS client id
+/// * `client_secret` - FYERS client secret
+/// * `refresh_token` - The refresh token returned with the original access token
+/// * `pin` - The user's FYERS PIN
+pub async fn refresh_access_token(
+    client_id: &str,
+    client_secret: &str,
+    refresh_token: &str,
+    pin: &str,
+) -> Result<String, FyersError> {
+    let app_id_hash = app_id_hash(client_id, client_secret);
+    let request_body = RefreshTokenRequest {
+        grant_type: "refresh_token",
+        app_id_hash: &app_id_hash,
+        refresh_token,
+        pin,
+    };
+
+    let url = format!("{}/validate-refresh-token", FYERS_API_BASE_URL);
+    let (access_token, _) = request_tokens(&url, &request_body).await?;
+    Ok(access_token)
+}
+
+/// # Description
+/// Refresh the token in a token store if it expires within `margin`, and save the new access
+/// token back to the store. Clients created with `from_token_store` pick up the new token on
+/// their next call. Returns the token that is now in the store.
+///
+/// # Arguments
+/// * `client_id` - FYERS client id
+/// * `client_secret` - FYERS client secret
+/// * `pin` - The user's FYERS PIN
+/// * `token_store` - The token store holding a token with a refresh token
+/// * `margin` - Refresh when the access token expires within this duration
+pub async fn refresh_stored_token(
+    client_id: &str,
+    client_secret: &str,
+    pin: &str,
+    token_store: &dyn TokenStore,
+    margin: Duration,
+) -> Result<StoredToken, FyersError> {
+    let token = token_store
+        .load()?
+        .ok_or_else(|| FyersError::AuthError("No access token found in the token store".to_string()))?;
+    if !token.expires_within(margin) {
+        return Ok(token);
+    }
+
+    let refresh_token = token.refresh_token.ok_or_else(|| {
+        FyersError::AuthError("The stored token has no refresh token, log in again".to_string())
+    })?;
+    let access_token = refresh_access_token(client_id, client_secret, &refresh_token, pin).await?;
+
+    let mut refreshed = StoredToken::from_access_token(&access_token)?;
+    refreshed.refresh_token = Some(refresh_token);
+    token_store.save(&refreshed)?;
+    Ok(refreshed)
+}
+
+/// # Description
+/// Spawn a background task that keeps the token in a token store fresh for long running
+/// services, checking every `interval` and refreshing when the token expires within `margin`.
+/// Failures are logged with `log::warn!` and retried on the next check.
+///
+/// # Arguments
+/// * `client_id` - FYERS client id
+/// * `client_secret` - FYERS client secret
+/// * `pin` - The user's FYERS PIN
+/// * `token_store` - The token store shared with the clients
+/// * `interval` - How often to check the token
+/// * `margin` - Refresh when the access token expires within this duration
+pub fn spawn_token_refresher(
+    client_id: String,
+    client_secret: String,
+    pin: String,
+    token_store: Arc<dyn TokenStore>,
+    interval: Duration,
+    margin: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = refresh_stored_token(&client_id, &client_secret, &pin, token_store.as_ref(), margin).await {
+                log::warn!("Failed to refresh access token: {}", e);
+            }
+        }
+    })
+}
+
+/// Create SHA-256 hash of `client_id:secret_key`
+fn app_id_hash(client_id: &str, client_secret: &str) -> String {
     let to_hash = format!("{}:{}", client_id, client_secret);
     let mut hasher = Sha256::new();
     hasher.update(to_hash.as_bytes());
-    let app_id_hash = format!("{:x}", hasher.finalize());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Validate an auth code, returning the access token and refresh token
+async fn validate_auth_code(
+    client_id: &str,
+    client_secret: &str,
+    auth_code: &str,
+) -> Result<(String, Option<String>), FyersError> {
+    // The steps for authentication are outlined in the docs here:
+    // * https://myapi.fyers.in/docsv3#tag/Authentication-and-Login-Flow-User-Apps/paths/~1Authentication%20&%20Login%20Flow%20-%20User%20Apps/patch
+
+    // 1. Create SHA-256 hash of `client_id:secret_key`
+    let app_id_hash = app_id_hash(client_id, client_secret);
 
     // 2. Construct the JSON request body
     let request_body = TokenRequest {
@@ -67,20 +328,26 @@ pub async fn generate_access_token(
 
     // 3. Make POST request to the `/validate-authcode` endpoint
     let url = format!("{}/validate-authcode", FYERS_API_BASE_URL);
+    request_tokens(&url, &request_body).await
+}
+
+/// POST a token request and extract the access token and refresh token from the response
+async fn request_tokens<T: Serialize>(url: &str, request_body: &T) -> Result<(String, Option<String>), FyersError> {
     let client = Client::new();
     let response = client
-        .post(&url)
-        .json(&request_body)
+        .post(url)
+        .json(request_body)
         .send()
         .await?;
 
-    // 4. Parse the response and extract `access_token`
+    // Parse the response and extract `access_token` and `refresh_token`
     if response.status().is_success() {
         let token_response = response.json::<TokenResponse>().await?;
         if token_response.s == "ok" {
-            token_response.access_token.ok_or_else(|| {
+            let access_token = token_response.access_token.ok_or_else(|| {
                 FyersError::AuthError("API returned ok, but no access token was found".to_string())
-            })
+            })?;
+            Ok((access_token, token_response.refresh_token))
         } else {
             Err(FyersError::ApiError { 
                 s: token_response.s,
@@ -98,3 +365,84 @@ pub async fn generate_access_token(
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REDIRECT_URI: &str = "http://127.0.0.1:8080/callback";
+
+    fn redirect(session: &LoginSession, query: &str) -> Result<String, FyersError> {
+        session.parse_redirect(&format!("{}?{}", REDIRECT_URI, query))
+    }
+
+    #[test]
+    fn accepts_a_redirect_with_the_session_state() {
+        let session = LoginSession::new("APP-100", REDIRECT_URI);
+        let query = format!("s=ok&code=200&auth_code=abc&state={}", session.state());
+        assert_eq!(redirect(&session, &query).unwrap(), "abc");
+    }
+
+    #[test]
+    fn rejects_a_state_mismatch() {
+        let session = LoginSession::new("APP-100", REDIRECT_URI);
+        let other = LoginSession::new("APP-100", REDIRECT_URI);
+        assert_ne!(session.state(), other.state());
+
+        for query in [format!("s=ok&auth_code=abc&state={}", other.state()), "s=ok&auth_code=abc".to_string()] {
+            match redirect(&session, &query) {
+                Err(FyersError::AuthError(message)) => assert!(message.contains("state")),
+                other => panic!("expected a state error, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_a_missing_auth_code() {
+        let session = LoginSession::new("APP-100", REDIRECT_URI);
+        for query in [format!("s=ok&state={}", session.state()), format!("s=ok&auth_code=&state={}", session.state())] {
+            match redirect(&session, &query) {
+                Err(FyersError::AuthError(message)) => assert!(message.contains("auth_code")),
+                other => panic!("expected a missing auth_code error, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn reports_error_redirects() {
+        let session = LoginSession::new("APP-100", REDIRECT_URI);
+        let query = format!("s=error&message=User%20cancelled&state={}", session.state());
+        match redirect(&session, &query) {
+            Err(FyersError::AuthError(message)) => assert_eq!(message, "Login failed: User cancelled"),
+            other => panic!("expected a login error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn encodes_the_redirect_uri_in_the_auth_url() {
+        let redirect_uri = "http://127.0.0.1:8080/cb?next=a b&x=1";
+        let auth_url = generate_auth_url("APP-100", redirect_uri, "st");
+        assert!(!auth_url.contains("&x=1"));
+        assert!(!auth_url.contains(' '));
+
+        let url = Url::parse(&auth_url).unwrap();
+        let params: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+        assert_eq!(
+            params,
+            [
+                ("client_id".to_string(), "APP-100".to_string()),
+                ("redirect_uri".to_string(), redirect_uri.to_string()),
+                ("response_type".to_string(), "code".to_string()),
+                ("state".to_string(), "st".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn constant_time_eq_compares_lengths() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"abcd"));
+        assert!(!constant_time_eq(b"", b"a"));
+        assert!(constant_time_eq(b"", b""));
+    }
+}
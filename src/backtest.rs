@@ -0,0 +1,588 @@
+use crate::charges::{ChargeTable, Charges, Segment};
+use crate::models::Candle;
+use crate::paper::exchange_and_segment;
+use std::collections::{HashMap, VecDeque};
+
+/// Settings of a backtest run
+#[derive(Debug, Clone, Copy)]
+pub struct BacktestConfig {
+    pub initial_capital: f64,
+    /// Slippage applied to market fills, against the order, as a fraction of the price
+    pub slippage: f64,
+    /// Rates used to compute the charges of every fill, by the segment of its symbol. Equity
+    /// fills are charged as intraday; set `equity_intraday` to the delivery rates to charge them
+    /// as delivery.
+    pub charges: ChargeTable,
+    /// Close the open positions at the last close of their symbol when the replay ends
+    pub close_at_end: bool,
+}
+
+impl Default for BacktestConfig {
+    fn default() -> Self {
+        Self {
+            initial_capital: 100_000.0,
+            slippage: 0.0,
+            charges: ChargeTable::default(),
+            close_at_end: true,
+        }
+    }
+}
+
+/// A single fill of a backtest order
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fill {
+    pub timestamp: i64,
+    pub symbol: String,
+    /// 1 for a buy, -1 for a sell
+    pub side: i64,
+    pub qty: i64,
+    pub price: f64,
+    pub charges: Charges,
+}
+
+/// A closed round trip, matched FIFO from the fills
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClosedTrade {
+    pub symbol: String,
+    /// 1 for a long trade, -1 for a short trade
+    pub side: i64,
+    pub qty: i64,
+    pub entry_time: i64,
+    pub entry_price: f64,
+    pub exit_time: i64,
+    pub exit_price: f64,
+    pub gross_pnl: f64,
+    /// Charges of the entry and exit fills, pro rata to the quantity of the trade
+    pub charges: f64,
+    pub net_pnl: f64,
+}
+
+/// A point of the equity curve
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EquityPoint {
+    pub timestamp: i64,
+    pub equity: f64,
+    /// Fall from the highest equity so far, as a fraction of it
+    pub drawdown: f64,
+}
+
+/// Summary statistics of a backtest run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BacktestSummary {
+    pub initial_capital: f64,
+    pub final_equity: f64,
+    /// Return over the run, as a fraction of the initial capital
+    pub total_return: f64,
+    pub net_pnl: f64,
+    pub total_charges: f64,
+    pub trades: usize,
+    pub winners: usize,
+    pub losers: usize,
+    /// Fraction of the closed trades with a positive net P&L
+    pub win_rate: f64,
+    pub average_win: f64,
+    pub average_loss: f64,
+    /// Gross profit of the winners over the gross loss of the losers, net of charges
+    pub profit_factor: f64,
+    /// Largest fall from a peak of the equity curve, in rupees
+    pub max_drawdown: f64,
+    /// Largest fall from a peak of the equity curve, as a fraction of the peak
+    pub max_drawdown_pct: f64,
+}
+
+/// The result of a backtest run
+#[derive(Debug, Clone)]
+pub struct BacktestReport {
+    pub fills: Vec<Fill>,
+    pub trades: Vec<ClosedTrade>,
+    pub equity_curve: Vec<EquityPoint>,
+    pub summary: BacktestSummary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OrderKind {
+    Market,
+    Limit(f64),
+}
+
+#[derive(Debug, Clone)]
+struct PendingOrder {
+    symbol: String,
+    side: i64,
+    qty: i64,
+    kind: OrderKind,
+}
+
+// An open part of a position, waiting to be matched by an opposite fill
+#[derive(Debug, Clone)]
+struct Lot {
+    timestamp: i64,
+    qty: i64,
+    price: f64,
+    // Charges per unit of the fill that opened the lot
+    charges_per_unit: f64,
+}
+
+#[derive(Debug, Default)]
+struct SymbolState {
+    // Signed net quantity
+    position: i64,
+    // Open lots, all on the side of the position
+    lots: VecDeque<Lot>,
+    last_price: Option<f64>,
+}
+
+#[derive(Debug)]
+struct BacktestState {
+    config: BacktestConfig,
+    cash: f64,
+    symbols: HashMap<String, SymbolState>,
+    pending: Vec<PendingOrder>,
+    fills: Vec<Fill>,
+    trades: Vec<ClosedTrade>,
+}
+
+impl BacktestState {
+    fn equity(&self) -> f64 {
+        self.cash
+            + self
+                .symbols
+                .values()
+                .map(|s| s.position as f64 * s.last_price.unwrap_or(0.0))
+                .sum::<f64>()
+    }
+
+    fn fill(&mut self, timestamp: i64, symbol: &str, side: i64, qty: i64, price: f64) {
+        let (_, segment) = exchange_and_segment(symbol);
+        let segment = Segment::classify(segment, symbol, "INTRADAY");
+        let charges = self.config.charges.compute(segment, side, price * qty as f64);
+        self.cash -= side as f64 * qty as f64 * price + charges.total();
+        self.fills.push(Fill {
+            timestamp,
+            symbol: symbol.to_string(),
+            side,
+            qty,
+            price,
+            charges,
+        });
+
+        let charges_per_unit = charges.total() / qty as f64;
+        let state = self.symbols.entry(symbol.to_string()).or_default();
+        let mut remaining = qty;
+
+        // Close open lots of the opposite side first, oldest first
+        while remaining > 0 && state.position.signum() == -side {
+            let Some(lot) = state.lots.front_mut() else { break };
+            let matched = remaining.min(lot.qty);
+            let trade_side = -side;
+            let gross_pnl = (price - lot.price) * matched as f64 * trade_side as f64;
+            let charges = (lot.charges_per_unit + charges_per_unit) * matched as f64;
+            self.trades.push(ClosedTrade {
+                symbol: symbol.to_string(),
+                side: trade_side,
+                qty: matched,
+                entry_time: lot.timestamp,
+                entry_price: lot.price,
+                exit_time: timestamp,
+                exit_price: price,
+                gross_pnl,
+                charges,
+                net_pnl: gross_pnl - charges,
+            });
+
+            lot.qty -= matched;
+            if lot.qty == 0 {
+                state.lots.pop_front();
+            }
+            state.position += side * matched;
+            remaining -= matched;
+        }
+
+        // Whatever is left opens (or adds to) a position on this side
+        if remaining > 0 {
+            state.lots.push_back(Lot {
+                timestamp,
+                qty: remaining,
+                price,
+                charges_per_unit,
+            });
+            state.position += side * remaining;
+        }
+    }
+
+    // Fill the pending orders of `symbol` that the candle satisfies
+    fn match_orders(&mut self, symbol: &str, candle: &Candle) {
+        let slippage = self.config.slippage;
+        let mut index = 0;
+        while index < self.pending.len() {
+            let order = &self.pending[index];
+            if order.symbol != symbol {
+                index += 1;
+                continue;
+            }
+            let buy = order.side == 1;
+            let price = match order.kind {
+                OrderKind::Market if buy => Some(candle.open() * (1.0 + slippage)),
+                OrderKind::Market => Some(candle.open() * (1.0 - slippage)),
+                // A limit fills at its price, or at the open when the candle gaps through it
+                OrderKind::Limit(limit) if buy && candle.low() <= limit => Some(candle.open().min(limit)),
+                OrderKind::Limit(limit) if !buy && candle.high() >= limit => Some(candle.open().max(limit)),
+                OrderKind::Limit(_) => None,
+            };
+            match price {
+                Some(price) => {
+                    let order = self.pending.remove(index);
+                    self.fill(candle.timestamp(), &order.symbol, order.side, order.qty, price);
+                }
+                None => index += 1,
+            }
+        }
+    }
+}
+
+/// What a strategy sees of the backtest when it is called, and how it places orders. Orders are
+/// filled from the next candle of their symbol: market orders at its open, limit orders once its
+/// range reaches the limit.
+pub struct BacktestContext<'a> {
+    state: &'a mut BacktestState,
+    timestamp: i64,
+}
+
+impl BacktestContext<'_> {
+    /// Time of the current candle, in epoch seconds
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    pub fn cash(&self) -> f64 {
+        self.state.cash
+    }
+
+    /// Cash plus the open positions marked to their last close
+    pub fn equity(&self) -> f64 {
+        self.state.equity()
+    }
+
+    /// Signed net quantity held in a symbol
+    pub fn position(&self, symbol: &str) -> i64 {
+        self.state.symbols.get(symbol).map_or(0, |s| s.position)
+    }
+
+    /// The last close of a symbol
+    pub fn last_price(&self, symbol: &str) -> Option<f64> {
+        self.state.symbols.get(symbol).and_then(|s| s.last_price)
+    }
+
+    /// Whether a symbol has an order waiting to be filled
+    pub fn has_pending(&self, symbol: &str) -> bool {
+        self.state.pending.iter().any(|order| order.symbol == symbol)
+    }
+
+    pub fn buy(&mut self, symbol: &str, qty: i64) {
+        self.submit(symbol, 1, qty, OrderKind::Market);
+    }
+
+    pub fn sell(&mut self, symbol: &str, qty: i64) {
+        self.submit(symbol, -1, qty, OrderKind::Market);
+    }
+
+    pub fn buy_limit(&mut self, symbol: &str, qty: i64, price: f64) {
+        self.submit(symbol, 1, qty, OrderKind::Limit(price));
+    }
+
+    pub fn sell_limit(&mut self, symbol: &str, qty: i64, price: f64) {
+        self.submit(symbol, -1, qty, OrderKind::Limit(price));
+    }
+
+    /// # Description
+    /// Close the position in a symbol at the next open, cancelling its pending orders
+    pub fn close(&mut self, symbol: &str) {
+        self.cancel(symbol);
+        let position = self.position(symbol);
+        if position != 0 {
+            self.submit(symbol, -position.signum(), position.abs(), OrderKind::Market);
+        }
+    }
+
+    /// # Description
+    /// Cancel the pending orders of a symbol
+    pub fn cancel(&mut self, symbol: &str) {
+        self.state.pending.retain(|order| order.symbol != symbol);
+    }
+
+    fn submit(&mut self, symbol: &str, side: i64, qty: i64, kind: OrderKind) {
+        if qty > 0 {
+            self.state.pending.push(PendingOrder {
+                symbol: symbol.to_string(),
+                side,
+                qty,
+                kind,
+            });
+        }
+    }
+}
+
+/// # Description
+/// An event driven backtest over historical candles. Candles of every symbol are replayed in time
+/// order and each one is passed to the strategy, which places orders through a
+/// [BacktestContext].
+#[derive(Debug, Clone, Default)]
+pub struct Backtest {
+    config: BacktestConfig,
+    candles: Vec<(String, Candle)>,
+}
+
+impl Backtest {
+    pub fn new(config: BacktestConfig) -> Self {
+        Self {
+            config,
+            candles: Vec::new(),
+        }
+    }
+
+    /// # Description
+    /// Add the candles of a symbol, e.g. from [DataApi::get_historical_data](crate::dataapi::DataApi::get_historical_data)
+    ///
+    /// # Arguments
+    /// * `symbol` - The symbol ticker
+    /// * `candles` - The candles, in any order
+    pub fn add_candles(&mut self, symbol: &str, candles: &[Candle]) {
+        self.candles
+            .extend(candles.iter().map(|candle| (symbol.to_string(), *candle)));
+    }
+
+    /// # Description
+    /// Replay the candles through a strategy
+    ///
+    /// # Arguments
+    /// * `strategy` - Called for every candle with the context, the symbol and the candle
+    pub fn run<F>(&self, mut strategy: F) -> BacktestReport
+    where
+        F: FnMut(&mut BacktestContext, &str, &Candle),
+    {
+        let mut events: Vec<&(String, Candle)> = self.candles.iter().collect();
+        // Stable, so candles with the same time keep the order they were added in
+        events.sort_by_key(|(_, candle)| candle.timestamp());
+
+        let mut state = BacktestState {
+            config: self.config,
+            cash: self.config.initial_capital,
+            symbols: HashMap::new(),
+            pending: Vec::new(),
+            fills: Vec::new(),
+            trades: Vec::new(),
+        };
+        let mut equity_curve: Vec<EquityPoint> = Vec::new();
+        let mut peak = self.config.initial_capital;
+
+        for (index, (symbol, candle)) in events.iter().enumerate() {
+            state.match_orders(symbol, candle);
+            state.symbols.entry(symbol.clone()).or_default().last_price = Some(candle.close());
+
+            let mut context = BacktestContext {
+                state: &mut state,
+                timestamp: candle.timestamp(),
+            };
+            strategy(&mut context, symbol, candle);
+
+            // One equity point per timestamp, once every symbol at that time has been replayed
+            let last_of_timestamp = events
+                .get(index + 1)
+                .is_none_or(|(_, next)| next.timestamp() != candle.timestamp());
+            if last_of_timestamp {
+                let equity = state.equity();
+                peak = peak.max(equity);
+                equity_curve.push(EquityPoint {
+                    timestamp: candle.timestamp(),
+                    equity,
+                    drawdown: if peak > 0.0 { (peak - equity) / peak } else { 0.0 },
+                });
+            }
+        }
+
+        if self.config.close_at_end {
+            let open: Vec<(String, i64, f64)> = state
+                .symbols
+                .iter()
+                .filter(|(_, s)| s.position != 0)
+                .filter_map(|(symbol, s)| s.last_price.map(|price| (symbol.clone(), s.position, price)))
+                .collect();
+            let timestamp = events.last().map_or(0, |(_, candle)| candle.timestamp());
+            for (symbol, position, price) in open {
+                state.fill(timestamp, &symbol, -position.signum(), position.abs(), price);
+            }
+            if let Some(last) = equity_curve.last_mut() {
+                last.equity = state.equity();
+                peak = peak.max(last.equity);
+                last.drawdown = if peak > 0.0 { (peak - last.equity) / peak } else { 0.0 };
+            }
+        }
+
+        let summary = summarize(&self.config, &state, &equity_curve);
+        BacktestReport {
+            fills: state.fills,
+            trades: state.trades,
+            equity_curve,
+            summary,
+        }
+    }
+}
+
+fn summarize(config: &BacktestConfig, state: &BacktestState, equity_curve: &[EquityPoint]) -> BacktestSummary {
+    let final_equity = state.equity();
+    let winners: Vec<f64> = state.trades.iter().map(|t| t.net_pnl).filter(|pnl| *pnl > 0.0).collect();
+    let losers: Vec<f64> = state.trades.iter().map(|t| t.net_pnl).filter(|pnl| *pnl <= 0.0).collect();
+    let gross_win: f64 = winners.iter().sum();
+    let gross_loss: f64 = -losers.iter().sum::<f64>();
+
+    let mut peak = config.initial_capital;
+    let mut max_drawdown: f64 = 0.0;
+    let mut max_drawdown_pct: f64 = 0.0;
+    for point in equity_curve {
+        peak = peak.max(point.equity);
+        max_drawdown = max_drawdown.max(peak - point.equity);
+        max_drawdown_pct = max_drawdown_pct.max(point.drawdown);
+    }
+
+    let mean = |values: &[f64]| if values.is_empty() { 0.0 } else { values.iter().sum::<f64>() / values.len() as f64 };
+    BacktestSummary {
+        initial_capital: config.initial_capital,
+        final_equity,
+        total_return: if config.initial_capital > 0.0 { final_equity / config.initial_capital - 1.0 } else { 0.0 },
+        net_pnl: final_equity - config.initial_capital,
+        total_charges: state.fills.iter().map(|fill| fill.charges.total()).sum(),
+        trades: state.trades.len(),
+        winners: winners.len(),
+        losers: losers.len(),
+        win_rate: if state.trades.is_empty() { 0.0 } else { winners.len() as f64 / state.trades.len() as f64 },
+        average_win: mean(&winners),
+        average_loss: mean(&losers),
+        profit_factor: match (gross_win > 0.0, gross_loss > 0.0) {
+            (_, true) => gross_win / gross_loss,
+            (true, false) => f64::INFINITY,
+            (false, false) => 0.0,
+        },
+        max_drawdown,
+        max_drawdown_pct,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SBIN: &str = "NSE:SBIN-EQ";
+
+    fn candle(timestamp: i64, open: f64, high: f64, low: f64, close: f64) -> Candle {
+        Candle(timestamp, open, high, low, close, 1_000)
+    }
+
+    fn config() -> BacktestConfig {
+        BacktestConfig {
+            initial_capital: 100_000.0,
+            slippage: 0.0,
+            charges: ChargeTable::zero(),
+            close_at_end: true,
+        }
+    }
+
+    #[test]
+    fn market_orders_fill_at_the_next_open_with_slippage() {
+        let mut backtest = Backtest::new(BacktestConfig { slippage: 0.001, ..config() });
+        backtest.add_candles(SBIN, &[candle(120, 102.0, 104.0, 101.0, 103.0), candle(60, 100.0, 101.0, 99.0, 100.0)]);
+        let report = backtest.run(|ctx, symbol, _| {
+            if ctx.position(symbol) == 0 && !ctx.has_pending(symbol) {
+                ctx.buy(symbol, 10);
+            }
+        });
+
+        let entry = &report.fills[0];
+        assert_eq!((entry.timestamp, entry.side, entry.qty), (120, 1, 10));
+        assert!((entry.price - 102.102).abs() < 1e-9);
+        // Closed at the last close when the replay ends
+        let exit = &report.fills[1];
+        assert_eq!((exit.side, exit.price), (-1, 103.0));
+        assert_eq!(report.trades.len(), 1);
+    }
+
+    #[test]
+    fn limit_orders_fill_at_the_limit_or_the_gap() {
+        let mut backtest = Backtest::new(config());
+        backtest.add_candles(
+            SBIN,
+            &[
+                candle(60, 100.0, 101.0, 99.0, 100.0),
+                candle(120, 100.0, 100.5, 99.5, 100.0),
+                candle(180, 99.0, 99.5, 97.0, 98.0),
+                candle(240, 94.0, 95.0, 93.0, 94.0),
+            ],
+        );
+        let report = backtest.run(|ctx, symbol, candle| {
+            if candle.timestamp() == 60 {
+                ctx.buy_limit(symbol, 1, 98.0);
+                ctx.buy_limit(symbol, 1, 95.0);
+            }
+        });
+
+        let prices: Vec<(i64, f64)> = report.fills.iter().filter(|f| f.side == 1).map(|f| (f.timestamp, f.price)).collect();
+        assert_eq!(prices, vec![(180, 98.0), (240, 94.0)]);
+    }
+
+    #[test]
+    fn round_trips_match_fifo_and_carry_their_charges() {
+        let mut backtest = Backtest::new(BacktestConfig {
+            charges: ChargeTable::default(),
+            ..config()
+        });
+        backtest.add_candles(
+            SBIN,
+            &[
+                candle(60, 100.0, 100.0, 100.0, 100.0),
+                candle(120, 100.0, 100.0, 100.0, 100.0),
+                candle(180, 110.0, 110.0, 110.0, 110.0),
+                candle(240, 120.0, 120.0, 120.0, 120.0),
+            ],
+        );
+        let report = backtest.run(|ctx, symbol, candle| match candle.timestamp() {
+            60 => ctx.buy(symbol, 10),
+            120 => ctx.buy(symbol, 10),
+            180 => ctx.sell(symbol, 15),
+            _ => {}
+        });
+
+        let trips: Vec<(i64, f64, f64)> = report.trades.iter().map(|t| (t.qty, t.entry_price, t.exit_price)).collect();
+        // Each order fills at the next open; the last 5 are closed when the replay ends
+        assert_eq!(trips, vec![(10, 100.0, 120.0), (5, 110.0, 120.0), (5, 110.0, 120.0)]);
+        assert_eq!(report.trades.iter().map(|t| t.gross_pnl).sum::<f64>(), 300.0);
+
+        let charges: f64 = report.trades.iter().map(|t| t.charges).sum();
+        let summary = report.summary;
+        assert!((charges - summary.total_charges).abs() < 1e-9);
+        assert!((summary.net_pnl - (300.0 - summary.total_charges)).abs() < 1e-9);
+        assert_eq!(summary.winners, 3);
+    }
+
+    #[test]
+    fn drawdown_is_measured_from_the_peak() {
+        let mut backtest = Backtest::new(config());
+        backtest.add_candles(
+            SBIN,
+            &[
+                candle(60, 100.0, 100.0, 100.0, 100.0),
+                candle(120, 100.0, 110.0, 100.0, 110.0),
+                candle(180, 110.0, 110.0, 88.0, 88.0),
+                candle(240, 90.0, 90.0, 90.0, 90.0),
+            ],
+        );
+        let report = backtest.run(|ctx, symbol, candle| {
+            if candle.timestamp() == 60 {
+                ctx.buy(symbol, 1_000);
+            }
+        });
+
+        // Bought at 120 for 100, peak at 110, trough at 88
+        assert_eq!(report.summary.max_drawdown, 22_000.0);
+        assert!((report.summary.max_drawdown_pct - 22_000.0 / 110_000.0).abs() < 1e-12);
+        assert_eq!(report.summary.net_pnl, -10_000.0);
+    }
+}
@@ -0,0 +1,179 @@
+use crate::credentials::Credentials;
+use crate::error::FyersError;
+use crate::models::orders::ModifyOrderRequest;
+use crate::models::{FundsResponse, OrdersResponse, PositionsResponse, SingleOrderRequest, SingleOrderResponse, TradesResponse};
+use crate::orders::Order;
+use crate::paper::PaperBroker;
+use crate::token_store::TokenStore;
+use crate::transaction::Transaction;
+use crate::user::User;
+use std::future::Future;
+use std::sync::Arc;
+
+/// # Description
+/// Order execution and account state, independent of where orders actually go. Strategy code
+/// written against this trait runs unchanged on the live API ([LiveBroker]), in paper trading
+/// ([PaperBroker]) or on any other implementation.
+pub trait Broker: Send + Sync {
+    /// Place an order, returning its id
+    fn place_order(&self, order: &SingleOrderRequest) -> impl Future<Output = Result<SingleOrderResponse, FyersError>> + Send;
+
+    /// Place an order that exits a position, e.g. for a [KillSwitch](crate::kill_switch::KillSwitch).
+    /// Wrappers that check or block orders pass exits through unchecked, so that a position can
+    /// always be closed. Defaults to [Broker::place_order].
+    fn place_exit_order(&self, order: &SingleOrderRequest) -> impl Future<Output = Result<SingleOrderResponse, FyersError>> + Send {
+        self.place_order(order)
+    }
+
+    /// Modify a pending order
+    fn modify_order(&self, request: &ModifyOrderRequest) -> impl Future<Output = Result<SingleOrderResponse, FyersError>> + Send;
+
+    /// Cancel a pending order
+    fn cancel_order(&self, id: &str) -> impl Future<Output = Result<SingleOrderResponse, FyersError>> + Send;
+
+    /// The orders of the day
+    fn orders(&self) -> impl Future<Output = Result<OrdersResponse, FyersError>> + Send;
+
+    /// The open and closed positions of the day
+    fn positions(&self) -> impl Future<Output = Result<PositionsResponse, FyersError>> + Send;
+
+    /// The trades of the day
+    fn trades(&self) -> impl Future<Output = Result<TradesResponse, FyersError>> + Send;
+
+    /// The fund limits of the account
+    fn funds(&self) -> impl Future<Output = Result<FundsResponse, FyersError>> + Send;
+}
+
+/// The live FYERS API as a [Broker]. Its clients share their credentials, so after
+/// [User::logout] every one of them fails with an auth error.
+#[derive(Debug, Clone)]
+pub struct LiveBroker {
+    pub order: Order,
+    pub transaction: Transaction,
+    pub user: User,
+}
+
+impl LiveBroker {
+    /// # Description
+    /// Create a live broker from an access token
+    ///
+    /// # Arguments
+    /// * `app_id` - The app id of the user.
+    /// * `access_token` - The access token of the user.
+    pub fn new(app_id: String, access_token: String) -> Self {
+        Self::with_credentials(Credentials::new(app_id, access_token))
+    }
+
+    /// # Description
+    /// Create a live broker that reads its access token from a token store
+    ///
+    /// # Arguments
+    /// * `app_id` - The app id of the user.
+    /// * `token_store` - The token store holding the access token of the user.
+    pub fn from_token_store(app_id: String, token_store: Arc<dyn TokenStore>) -> Result<Self, FyersError> {
+        Ok(Self::with_credentials(Credentials::from_token_store(app_id, token_store)?))
+    }
+
+    // The clients share one set of credentials, so logging out through `user` stops them all
+    fn with_credentials(credentials: Credentials) -> Self {
+        Self {
+            order: Order::with_credentials(credentials.clone()),
+            transaction: Transaction::with_credentials(credentials.clone()),
+            user: User::with_credentials(credentials),
+        }
+    }
+}
+
+impl Broker for LiveBroker {
+    async fn place_order(&self, order: &SingleOrderRequest) -> Result<SingleOrderResponse, FyersError> {
+        self.order.single_order(order).await
+    }
+
+    async fn modify_order(&self, request: &ModifyOrderRequest) -> Result<SingleOrderResponse, FyersError> {
+        self.order.modify_order(request).await
+    }
+
+    async fn cancel_order(&self, id: &str) -> Result<SingleOrderResponse, FyersError> {
+        self.order.cancel_order(id).await
+    }
+
+    async fn orders(&self) -> Result<OrdersResponse, FyersError> {
+        self.transaction.get_orders(None, None).await
+    }
+
+    async fn positions(&self) -> Result<PositionsResponse, FyersError> {
+        self.transaction.get_positions().await
+    }
+
+    async fn trades(&self) -> Result<TradesResponse, FyersError> {
+        self.transaction.get_trades(None).await
+    }
+
+    async fn funds(&self) -> Result<FundsResponse, FyersError> {
+        self.user.get_funds().await
+    }
+}
+
+impl Broker for PaperBroker {
+    async fn place_order(&self, order: &SingleOrderRequest) -> Result<SingleOrderResponse, FyersError> {
+        self.single_order(order)
+    }
+
+    async fn modify_order(&self, request: &ModifyOrderRequest) -> Result<SingleOrderResponse, FyersError> {
+        PaperBroker::modify_order(self, request)
+    }
+
+    async fn cancel_order(&self, id: &str) -> Result<SingleOrderResponse, FyersError> {
+        PaperBroker::cancel_order(self, id)
+    }
+
+    async fn orders(&self) -> Result<OrdersResponse, FyersError> {
+        Ok(self.get_orders())
+    }
+
+    async fn positions(&self) -> Result<PositionsResponse, FyersError> {
+        Ok(self.get_positions())
+    }
+
+    async fn trades(&self) -> Result<TradesResponse, FyersError> {
+        Ok(self.get_trades())
+    }
+
+    async fn funds(&self) -> Result<FundsResponse, FyersError> {
+        Ok(self.get_funds())
+    }
+}
+
+impl<B: Broker> Broker for Arc<B> {
+    fn place_order(&self, order: &SingleOrderRequest) -> impl Future<Output = Result<SingleOrderResponse, FyersError>> + Send {
+        (**self).place_order(order)
+    }
+
+    fn place_exit_order(&self, order: &SingleOrderRequest) -> impl Future<Output = Result<SingleOrderResponse, FyersError>> + Send {
+        (**self).place_exit_order(order)
+    }
+
+    fn modify_order(&self, request: &ModifyOrderRequest) -> impl Future<Output = Result<SingleOrderResponse, FyersError>> + Send {
+        (**self).modify_order(request)
+    }
+
+    fn cancel_order(&self, id: &str) -> impl Future<Output = Result<SingleOrderResponse, FyersError>> + Send {
+        (**self).cancel_order(id)
+    }
+
+    fn orders(&self) -> impl Future<Output = Result<OrdersResponse, FyersError>> + Send {
+        (**self).orders()
+    }
+
+    fn positions(&self) -> impl Future<Output = Result<PositionsResponse, FyersError>> + Send {
+        (**self).positions()
+    }
+
+    fn trades(&self) -> impl Future<Output = Result<TradesResponse, FyersError>> + Send {
+        (**self).trades()
+    }
+
+    fn funds(&self) -> impl Future<Output = Result<FundsResponse, FyersError>> + Send {
+        (**self).funds()
+    }
+}
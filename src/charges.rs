@@ -0,0 +1,447 @@
+use crate::error::FyersError;
+use crate::models::transaction::Trade;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The kinds of trade that are charged differently
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Segment {
+    EquityDelivery,
+    EquityIntraday,
+    EquityFutures,
+    EquityOptions,
+    CurrencyFutures,
+    CurrencyOptions,
+    CommodityFutures,
+    CommodityOptions,
+}
+
+impl Segment {
+    /// # Description
+    /// Classify a trade from its segment code, symbol and product type
+    ///
+    /// # Arguments
+    /// * `segment` - The FYERS segment code (10: capital market, 11: equity derivatives, 12:
+    ///   currency derivatives, 20: commodity)
+    /// * `symbol` - The symbol ticker, e.g. "NSE:NIFTY25SEP25000CE"
+    /// * `product_type` - The product type, e.g. "CNC" or "INTRADAY"
+    pub fn classify(segment: i64, symbol: &str, product_type: &str) -> Self {
+        let option = symbol.ends_with("CE") || symbol.ends_with("PE");
+        match segment {
+            11 if option => Segment::EquityOptions,
+            11 => Segment::EquityFutures,
+            12 if option => Segment::CurrencyOptions,
+            12 => Segment::CurrencyFutures,
+            20 if option => Segment::CommodityOptions,
+            20 => Segment::CommodityFutures,
+            _ if product_type.eq_ignore_ascii_case("CNC") || product_type.eq_ignore_ascii_case("MTF") => {
+                Segment::EquityDelivery
+            }
+            _ => Segment::EquityIntraday,
+        }
+    }
+
+    /// Classify a trade of the trade book
+    pub fn of_trade(trade: &Trade) -> Self {
+        Self::classify(trade.segment, &trade.symbol, &trade.product_type)
+    }
+}
+
+/// The rates used to compute the charges of an order. Rates are fractions of the turnover, e.g.
+/// 0.00025 for 0.025%.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ChargeRates {
+    /// Brokerage as a fraction of the turnover
+    pub brokerage_rate: f64,
+    /// Upper limit of the brokerage computed from `brokerage_rate`, in rupees
+    pub brokerage_cap: f64,
+    /// Flat brokerage of every order, in rupees, on top of the brokerage computed from the rate
+    #[serde(default)]
+    pub brokerage_flat: f64,
+    /// Securities transaction tax on buys
+    pub stt_buy: f64,
+    /// Securities transaction tax on sells
+    pub stt_sell: f64,
+    /// Exchange transaction charges
+    pub exchange_rate: f64,
+    /// SEBI turnover fee
+    pub sebi_rate: f64,
+    /// Stamp duty, charged on buys only
+    pub stamp_duty_buy: f64,
+    /// GST, charged on brokerage, exchange charges and the SEBI fee
+    pub gst_rate: f64,
+}
+
+impl ChargeRates {
+    /// Rates for equity delivery (CNC) on NSE
+    pub fn equity_delivery() -> Self {
+        Self {
+            brokerage_rate: 0.003,
+            brokerage_cap: 20.0,
+            brokerage_flat: 0.0,
+            stt_buy: 0.001,
+            stt_sell: 0.001,
+            exchange_rate: 0.0000297,
+            sebi_rate: 0.000001,
+            stamp_duty_buy: 0.00015,
+            gst_rate: 0.18,
+        }
+    }
+
+    /// Rates for intraday equity on NSE
+    pub fn equity_intraday() -> Self {
+        Self {
+            brokerage_rate: 0.0003,
+            brokerage_cap: 20.0,
+            brokerage_flat: 0.0,
+            stt_buy: 0.0,
+            stt_sell: 0.00025,
+            exchange_rate: 0.0000297,
+            sebi_rate: 0.000001,
+            stamp_duty_buy: 0.00003,
+            gst_rate: 0.18,
+        }
+    }
+
+    /// Rates for equity futures on NSE
+    pub fn equity_futures() -> Self {
+        Self {
+            brokerage_rate: 0.0003,
+            brokerage_cap: 20.0,
+            brokerage_flat: 0.0,
+            stt_buy: 0.0,
+            stt_sell: 0.0002,
+            exchange_rate: 0.0000173,
+            sebi_rate: 0.000001,
+            stamp_duty_buy: 0.00002,
+            gst_rate: 0.18,
+        }
+    }
+
+    /// Rates for equity options on NSE. The turnover of an option order is its premium.
+    pub fn equity_options() -> Self {
+        Self {
+            brokerage_rate: 0.0,
+            brokerage_cap: 0.0,
+            brokerage_flat: 20.0,
+            stt_buy: 0.0,
+            stt_sell: 0.001,
+            exchange_rate: 0.0003503,
+            sebi_rate: 0.000001,
+            stamp_duty_buy: 0.00003,
+            gst_rate: 0.18,
+        }
+    }
+
+    /// Rates for currency futures on NSE
+    pub fn currency_futures() -> Self {
+        Self {
+            brokerage_rate: 0.0003,
+            brokerage_cap: 20.0,
+            brokerage_flat: 0.0,
+            stt_buy: 0.0,
+            stt_sell: 0.0,
+            exchange_rate: 0.0000035,
+            sebi_rate: 0.000001,
+            stamp_duty_buy: 0.000001,
+            gst_rate: 0.18,
+        }
+    }
+
+    /// Rates for currency options on NSE
+    pub fn currency_options() -> Self {
+        Self {
+            brokerage_rate: 0.0,
+            brokerage_cap: 0.0,
+            brokerage_flat: 20.0,
+            stt_buy: 0.0,
+            stt_sell: 0.0,
+            exchange_rate: 0.000311,
+            sebi_rate: 0.000001,
+            stamp_duty_buy: 0.000001,
+            gst_rate: 0.18,
+        }
+    }
+
+    /// Rates for non-agricultural commodity futures on MCX. `stt_sell` is the CTT.
+    pub fn commodity_futures() -> Self {
+        Self {
+            brokerage_rate: 0.0003,
+            brokerage_cap: 20.0,
+            brokerage_flat: 0.0,
+            stt_buy: 0.0,
+            stt_sell: 0.0001,
+            exchange_rate: 0.000021,
+            sebi_rate: 0.000001,
+            stamp_duty_buy: 0.00002,
+            gst_rate: 0.18,
+        }
+    }
+
+    /// Rates for commodity options on MCX. `stt_sell` is the CTT.
+    pub fn commodity_options() -> Self {
+        Self {
+            brokerage_rate: 0.0,
+            brokerage_cap: 0.0,
+            brokerage_flat: 20.0,
+            stt_buy: 0.0,
+            stt_sell: 0.0005,
+            exchange_rate: 0.000418,
+            sebi_rate: 0.000001,
+            stamp_duty_buy: 0.00003,
+            gst_rate: 0.18,
+        }
+    }
+
+    /// No charges at all
+    pub fn zero() -> Self {
+        Self {
+            brokerage_rate: 0.0,
+            brokerage_cap: 0.0,
+            brokerage_flat: 0.0,
+            stt_buy: 0.0,
+            stt_sell: 0.0,
+            exchange_rate: 0.0,
+            sebi_rate: 0.0,
+            stamp_duty_buy: 0.0,
+            gst_rate: 0.0,
+        }
+    }
+
+    /// # Description
+    /// The charges of a single order
+    ///
+    /// # Arguments
+    /// * `side` - 1 for a buy, -1 for a sell
+    /// * `turnover` - Quantity times price of the order
+    pub fn compute(&self, side: i64, turnover: f64) -> Charges {
+        let buy = side == 1;
+        let brokerage = (turnover * self.brokerage_rate).min(self.brokerage_cap) + self.brokerage_flat;
+        let stt = turnover * if buy { self.stt_buy } else { self.stt_sell };
+        let exchange = turnover * self.exchange_rate;
+        let sebi = turnover * self.sebi_rate;
+        let stamp_duty = if buy { turnover * self.stamp_duty_buy } else { 0.0 };
+        let gst = (brokerage + exchange + sebi) * self.gst_rate;
+        Charges {
+            brokerage,
+            stt,
+            exchange,
+            sebi,
+            stamp_duty,
+            gst,
+        }
+    }
+}
+
+impl Default for ChargeRates {
+    fn default() -> Self {
+        Self::equity_intraday()
+    }
+}
+
+/// # Description
+/// The charge rates of every segment. The defaults are the rates in force when this was written;
+/// when regulations or the brokerage plan change, load an updated table from JSON. Segments
+/// missing from the JSON keep their default rates.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChargeTable {
+    pub equity_delivery: ChargeRates,
+    pub equity_intraday: ChargeRates,
+    pub equity_futures: ChargeRates,
+    pub equity_options: ChargeRates,
+    pub currency_futures: ChargeRates,
+    pub currency_options: ChargeRates,
+    pub commodity_futures: ChargeRates,
+    pub commodity_options: ChargeRates,
+}
+
+impl Default for ChargeTable {
+    fn default() -> Self {
+        Self {
+            equity_delivery: ChargeRates::equity_delivery(),
+            equity_intraday: ChargeRates::equity_intraday(),
+            equity_futures: ChargeRates::equity_futures(),
+            equity_options: ChargeRates::equity_options(),
+            currency_futures: ChargeRates::currency_futures(),
+            currency_options: ChargeRates::currency_options(),
+            commodity_futures: ChargeRates::commodity_futures(),
+            commodity_options: ChargeRates::commodity_options(),
+        }
+    }
+}
+
+impl ChargeTable {
+    /// No charges in any segment
+    pub fn zero() -> Self {
+        let zero = ChargeRates::zero();
+        Self {
+            equity_delivery: zero,
+            equity_intraday: zero,
+            equity_futures: zero,
+            equity_options: zero,
+            currency_futures: zero,
+            currency_options: zero,
+            commodity_futures: zero,
+            commodity_options: zero,
+        }
+    }
+
+    /// # Description
+    /// Load a table from JSON, e.g. `{"equity_intraday": {"brokerage_rate": 0.0003, ...}}`
+    pub fn from_json(json: &str) -> Result<Self, FyersError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// # Description
+    /// Load a table from a JSON file
+    ///
+    /// # Arguments
+    /// * `path` - Path of the JSON file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, FyersError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn rates(&self, segment: Segment) -> &ChargeRates {
+        match segment {
+            Segment::EquityDelivery => &self.equity_delivery,
+            Segment::EquityIntraday => &self.equity_intraday,
+            Segment::EquityFutures => &self.equity_futures,
+            Segment::EquityOptions => &self.equity_options,
+            Segment::CurrencyFutures => &self.currency_futures,
+            Segment::CurrencyOptions => &self.currency_options,
+            Segment::CommodityFutures => &self.commodity_futures,
+            Segment::CommodityOptions => &self.commodity_options,
+        }
+    }
+
+    pub fn rates_mut(&mut self, segment: Segment) -> &mut ChargeRates {
+        match segment {
+            Segment::EquityDelivery => &mut self.equity_delivery,
+            Segment::EquityIntraday => &mut self.equity_intraday,
+            Segment::EquityFutures => &mut self.equity_futures,
+            Segment::EquityOptions => &mut self.equity_options,
+            Segment::CurrencyFutures => &mut self.currency_futures,
+            Segment::CurrencyOptions => &mut self.currency_options,
+            Segment::CommodityFutures => &mut self.commodity_futures,
+            Segment::CommodityOptions => &mut self.commodity_options,
+        }
+    }
+
+    /// # Description
+    /// The charges of a single order
+    ///
+    /// # Arguments
+    /// * `segment` - The segment of the order
+    /// * `side` - 1 for a buy, -1 for a sell
+    /// * `turnover` - Quantity times price of the order (the premium, for options)
+    pub fn compute(&self, segment: Segment, side: i64, turnover: f64) -> Charges {
+        self.rates(segment).compute(side, turnover)
+    }
+
+    /// # Description
+    /// The charges of a trade of the trade book, treated as a whole order
+    pub fn for_trade(&self, trade: &Trade) -> Charges {
+        self.compute(Segment::of_trade(trade), trade.side, trade.trade_price * trade.traded_qty as f64)
+    }
+}
+
+/// The charges of an order, in rupees
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Charges {
+    pub brokerage: f64,
+    /// STT, or CTT for commodities
+    pub stt: f64,
+    pub exchange: f64,
+    pub sebi: f64,
+    pub stamp_duty: f64,
+    pub gst: f64,
+}
+
+impl Charges {
+    pub fn total(&self) -> f64 {
+        self.brokerage + self.stt + self.exchange + self.sebi + self.stamp_duty + self.gst
+    }
+}
+
+impl std::ops::Add for Charges {
+    type Output = Charges;
+
+    fn add(self, other: Charges) -> Charges {
+        Charges {
+            brokerage: self.brokerage + other.brokerage,
+            stt: self.stt + other.stt,
+            exchange: self.exchange + other.exchange,
+            sebi: self.sebi + other.sebi,
+            stamp_duty: self.stamp_duty + other.stamp_duty,
+            gst: self.gst + other.gst,
+        }
+    }
+}
+
+impl std::ops::AddAssign for Charges {
+    fn add_assign(&mut self, other: Charges) {
+        *self = *self + other;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn brokerage_is_capped_rate_plus_flat_fee() {
+        let intraday = ChargeRates::equity_intraday();
+        assert!(close(intraday.compute(1, 10_000.0).brokerage, 3.0));
+        assert!(close(intraday.compute(1, 1_000_000.0).brokerage, 20.0));
+
+        let options = ChargeRates::equity_options();
+        assert!(close(options.compute(1, 500.0).brokerage, 20.0));
+        assert!(close(options.compute(1, 5_000_000.0).brokerage, 20.0));
+
+        // A zero rate without a flat fee is free brokerage, not a flat fee of the cap
+        let mut free = ChargeRates::equity_intraday();
+        free.brokerage_rate = 0.0;
+        assert_eq!(free.compute(1, 100_000.0).brokerage, 0.0);
+    }
+
+    #[test]
+    fn intraday_round_trip_charges() {
+        let table = ChargeTable::default();
+        let buy = table.compute(Segment::EquityIntraday, 1, 150_000.0);
+        let sell = table.compute(Segment::EquityIntraday, -1, 150_000.0);
+
+        assert!(close(buy.stt, 0.0));
+        assert!(close(sell.stt, 37.5));
+        assert!(close(buy.stamp_duty, 4.5));
+        assert!(close(sell.stamp_duty, 0.0));
+        assert!(close(buy.gst, (20.0 + 150_000.0 * 0.0000297 + 0.15) * 0.18));
+        let total = buy + sell;
+        assert!(close(total.total(), buy.total() + sell.total()));
+    }
+
+    #[test]
+    fn classifies_trades_by_segment_and_product() {
+        assert_eq!(Segment::classify(10, "NSE:SBIN-EQ", "CNC"), Segment::EquityDelivery);
+        assert_eq!(Segment::classify(10, "NSE:SBIN-EQ", "INTRADAY"), Segment::EquityIntraday);
+        assert_eq!(Segment::classify(11, "NSE:NIFTY25SEPFUT", "MARGIN"), Segment::EquityFutures);
+        assert_eq!(Segment::classify(11, "NSE:NIFTY25SEP25000CE", "MARGIN"), Segment::EquityOptions);
+        assert_eq!(Segment::classify(12, "NSE:USDINR25SEP83.5PE", "MARGIN"), Segment::CurrencyOptions);
+        assert_eq!(Segment::classify(20, "MCX:CRUDEOIL25SEPFUT", "MARGIN"), Segment::CommodityFutures);
+    }
+
+    #[test]
+    fn tables_load_partially_from_json() {
+        let table = ChargeTable::from_json(
+            r#"{"equity_intraday": {"brokerage_rate": 0.0, "brokerage_cap": 0.0, "stt_buy": 0.0,
+                "stt_sell": 0.0, "exchange_rate": 0.0, "sebi_rate": 0.0, "stamp_duty_buy": 0.0, "gst_rate": 0.0}}"#,
+        )
+        .unwrap();
+        assert_eq!(table.equity_intraday, ChargeRates::zero());
+        assert_eq!(table.equity_options, ChargeRates::equity_options());
+    }
+}
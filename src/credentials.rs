@@ -0,0 +1,158 @@
+use crate::error::FyersError;
+use crate::token_store::{unix_now, StoredToken, TokenStore};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long a token read from a token store is reused before the store is read again, so that
+/// requests do not block on the store
+const TOKEN_CACHE_TTL: Duration = Duration::from_secs(30);
+
+// A token read from the token store, as (access token, expiry, time it was read)
+type CachedToken = (String, i64, Instant);
+
+/// The app id and access token a client authenticates with. When a token store is configured the
+/// token is re-read from the store at most every [TOKEN_CACHE_TTL] (and as soon as the cached
+/// token has expired), so a token saved by another client (e.g. after a refresh) is picked up
+/// without rebuilding the client.
+#[derive(Debug, Clone)]
+pub(crate) struct Credentials {
+    app_id: String,
+    access_token: String,
+    // Expiry of `access_token`, if it could be read from the token
+    expires_at: Option<i64>,
+    token_store: Option<Arc<dyn TokenStore>>,
+    // Last token read from `token_store`, shared with clones of the client
+    cached: Arc<Mutex<Option<CachedToken>>>,
+    // The access token revoked by logout, shared with clones of the client. Only this token is
+    // refused, so a store-backed client works again once a new token is saved to the store.
+    revoked: Arc<Mutex<Option<String>>>,
+}
+
+impl Credentials {
+    pub(crate) fn new(app_id: String, access_token: String) -> Self {
+        let expires_at = StoredToken::from_access_token(&access_token)
+            .ok()
+            .map(|token| token.expires_at);
+        Self {
+            app_id,
+            access_token,
+            expires_at,
+            token_store: None,
+            cached: Arc::new(Mutex::new(None)),
+            revoked: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub(crate) fn from_token_store(app_id: String, token_store: Arc<dyn TokenStore>) -> Result<Self, FyersError> {
+        let token = token_store
+            .load()?
+            .ok_or_else(|| FyersError::AuthError("No access token found in the token store".to_string()))?;
+        Ok(Self {
+            app_id,
+            access_token: token.access_token.clone(),
+            expires_at: Some(token.expires_at),
+            token_store: Some(token_store),
+            cached: Arc::new(Mutex::new(Some((token.access_token, token.expires_at, Instant::now())))),
+            revoked: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// Mark the current token as revoked and remove it from the token store, if there is one
+    pub(crate) fn revoke(&self) -> Result<(), FyersError> {
+        let token = self
+            .current_token()
+            .map_or_else(|_| self.access_token.clone(), |(token, _)| token);
+        *self.revoked.lock().unwrap_or_else(|e| e.into_inner()) = Some(token);
+        *self.cached.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        match &self.token_store {
+            Some(store) => store.clear(),
+            None => Ok(()),
+        }
+    }
+
+    // The access token to use and its expiry, from the token store when there is one
+    fn current_token(&self) -> Result<(String, Option<i64>), FyersError> {
+        let Some(store) = &self.token_store else {
+            return Ok((self.access_token.clone(), self.expires_at));
+        };
+        let mut cached = self.cached.lock().unwrap_or_else(|e| e.into_inner());
+        match cached.as_ref() {
+            Some((token, expires_at, read_at)) if read_at.elapsed() < TOKEN_CACHE_TTL && unix_now() < *expires_at => {
+                Ok((token.clone(), Some(*expires_at)))
+            }
+            _ => {
+                let token = store
+                    .load()?
+                    .ok_or_else(|| FyersError::AuthError("No access token found in the token store".to_string()))?;
+                *cached = Some((token.access_token.clone(), token.expires_at, Instant::now()));
+                Ok((token.access_token, Some(token.expires_at)))
+            }
+        }
+    }
+
+    /// The value of the `Authorization` header, or an auth error if the token has expired or
+    /// has been revoked
+    pub(crate) fn auth_header(&self) -> Result<String, FyersError> {
+        let (access_token, expires_at) = self.current_token()?;
+        if self.revoked.lock().unwrap_or_else(|e| e.into_inner()).as_deref() == Some(access_token.as_str()) {
+            // Read the store again next time, a new login may have replaced the token
+            *self.cached.lock().unwrap_or_else(|e| e.into_inner()) = None;
+            return Err(FyersError::AuthError(
+                "Access token has been revoked by logout, log in again".to_string(),
+            ));
+        }
+
+        if let Some(expires_at) = expires_at {
+            if unix_now() >= expires_at {
+                return Err(FyersError::AuthError(format!(
+                    "Access token expired at {} (epoch seconds), log in again",
+                    expires_at
+                )));
+            }
+        }
+
+        Ok(format!("{}:{}", self.app_id, access_token))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token_store::MemoryTokenStore;
+
+    fn token(access_token: &str) -> StoredToken {
+        StoredToken {
+            access_token: access_token.to_string(),
+            issued_at: unix_now(),
+            expires_at: unix_now() + 3_600,
+            fy_id: None,
+            refresh_token: None,
+        }
+    }
+
+    #[test]
+    fn store_backed_clients_recover_after_a_new_login() {
+        let store: Arc<dyn TokenStore> = Arc::new(MemoryTokenStore::new());
+        store.save(&token("first")).unwrap();
+        let credentials = Credentials::from_token_store("APP-100".to_string(), store.clone()).unwrap();
+        let clone = credentials.clone();
+        assert_eq!(credentials.auth_header().unwrap(), "APP-100:first");
+
+        credentials.revoke().unwrap();
+        assert!(clone.auth_header().is_err());
+        // The revoked token is refused even if it is saved again
+        store.save(&token("first")).unwrap();
+        assert!(clone.auth_header().is_err());
+
+        store.save(&token("second")).unwrap();
+        assert_eq!(clone.auth_header().unwrap(), "APP-100:second");
+    }
+
+    #[test]
+    fn token_clients_stay_revoked() {
+        let credentials = Credentials::new("APP-100".to_string(), "token".to_string());
+        assert_eq!(credentials.auth_header().unwrap(), "APP-100:token");
+        credentials.revoke().unwrap();
+        assert!(credentials.auth_header().is_err());
+    }
+}
@@ -1,17 +1,27 @@
+use crate::credentials::Credentials;
 use crate::error::FyersError;
+use crate::token_store::TokenStore;
 use crate::models::{ HistoryResponse, MarketDepthResponse, QuoteResponse, OptionChainResponse };
+use crate::models::dataapi::{MarketStatusResponse, QuoteData, RawQuoteResponse, RawSymbolQuote};
+use crate::rate_limiter::RateLimiter;
+use futures_util::future::join_all;
 use reqwest::Client;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
 /// The DataApi Class. Implements the [Data Api](https://myapi.fyers.in/docsv3#tag/Data-Api) section of the official Fyers API.
 #[derive(Debug, Clone)]
 pub struct DataApi {
     http_client: Client,
-    app_id: String,
-    access_token: String,
+    credentials: Credentials,
+    rate_limiter: Arc<RateLimiter>,
 }
 
 const DATA_API_BASE_URL: &str = "https://api-t1.fyers.in/data";
 
+/// The maximum number of symbols the /quotes endpoint accepts in one request
+pub const MAX_QUOTE_SYMBOLS: usize = 50;
+
 impl DataApi {
 
     /// # Description
@@ -23,11 +33,46 @@ impl DataApi {
     pub fn new(app_id: String, access_token: String) -> Self {
         Self {
             http_client: Client::new(),
-            app_id,
-            access_token,
+            credentials: Credentials::new(app_id, access_token),
+            rate_limiter: Arc::new(RateLimiter::default()),
         }
     }
 
+    /// # Description
+    /// Create a new instance of the DataApi class that reads its access token from a token store.
+    /// Calls fail with an auth error once the stored token has expired.
+    ///
+    /// # Arguments
+    /// * `app_id` - The app id of the user.
+    /// * `token_store` - The token store holding the access token of the user.
+    pub fn from_token_store(app_id: String, token_store: Arc<dyn TokenStore>) -> Result<Self, FyersError> {
+        Ok(Self {
+            http_client: Client::new(),
+            credentials: Credentials::from_token_store(app_id, token_store)?,
+            rate_limiter: Arc::new(RateLimiter::default()),
+        })
+    }
+
+    /// Create a client that shares its credentials, and so its logout, with other clients
+    pub(crate) fn with_credentials(credentials: Credentials) -> Self {
+        Self {
+            http_client: Client::new(),
+            credentials,
+            rate_limiter: Arc::new(RateLimiter::default()),
+        }
+    }
+
+    /// # Description
+    /// Use a custom rate limiter for the calls that fan out into several requests. Pass the same
+    /// limiter to several clients to limit them together.
+    ///
+    /// # Arguments
+    /// * `rate_limiter` - The rate limiter to use
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
     /// # Description
     /// Get Historical data (up to date) for a given symbol. Record is presented in the form of
     /// candle data. [Read more](https://myapi.fyers.in/docsv3#tag/Data-Api/paths/~1DataApi/post)
@@ -54,7 +99,7 @@ impl DataApi {
         let url = format!("{}/history?symbol={}&resolution={}&date_format={}&range_from={}&range_to={}&cont_flag={}&oi_Flag={}",
             DATA_API_BASE_URL, symbol, resolution, date_format, range_from, range_to, cont_flag, oi_flag
             );
-        let auth_header_value = format!("{}:{}", self.app_id, self.access_token);
+        let auth_header_value = self.credentials.auth_header()?;
         let curl_command = format!(
             "curl -H \"Authorization: {}\" \"{}\"",
             auth_header_value, url
@@ -108,7 +153,7 @@ impl DataApi {
     /// * `symbols` - Symbols for which data is to be fetched (e.g. "NSE:SBIN-EQ", "NSE:RELIANCE-EQ,NSE:SBIN-EQ")
     pub async fn get_market_quotes(&self, symbols: &str) -> Result<QuoteResponse, FyersError> {
         let url = format!("{}/quotes?symbols={}", DATA_API_BASE_URL, symbols);
-        let auth_header_value = format!("{}:{}", self.app_id, self.access_token);
+        let auth_header_value = self.credentials.auth_header()?;
         let curl_command = format!(
             "curl -H \"Authorization: {}\" \"{}\"",
             auth_header_value, url
@@ -151,6 +196,39 @@ impl DataApi {
         Ok(quote_response)
     }
 
+    /// # Description
+    /// The status (open, pre-open, closed, ...) of every exchange segment and market type.
+    /// [Read more](https://myapi.fyers.in/docsv3#tag/Data-Api)
+    pub async fn get_market_status(&self) -> Result<MarketStatusResponse, FyersError> {
+        let url = format!("{}/marketStatus", DATA_API_BASE_URL);
+        let auth_header_value = self.credentials.auth_header()?;
+        let response = self
+            .http_client
+            .get(&url)
+            .header("Authorization", auth_header_value)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(FyersError::Network(
+                response.error_for_status().unwrap_err(),
+            ));
+        }
+
+        let response_text = response.text().await?;
+        let status_response: MarketStatusResponse = serde_json::from_str(&response_text)?;
+
+        if status_response.s != "ok" {
+            return Err(FyersError::ApiError {
+                s: status_response.s,
+                code: status_response.code,
+                message: status_response.message,
+            });
+        }
+
+        Ok(status_response)
+    }
+
     /// # Description
     /// Market Depth for one symbol provided by the user. [Read more](https://myapi.fyers.in/docsv3#tag/Data-Api/paths/~1DataApi/put)
     ///
@@ -160,7 +238,7 @@ impl DataApi {
     /// quantity
     pub async fn get_market_depth(&self, symbol: &str, ohlcv_flag: &str) -> Result<MarketDepthResponse, FyersError> {
         let url = format!("{}/depth?symbol={}&ohlcv_flag={}", DATA_API_BASE_URL, symbol, ohlcv_flag);
-        let auth_header_value = format!("{}:{}", self.app_id, self.access_token);
+        let auth_header_value = self.credentials.auth_header()?;
         let curl_command = format!(
             "curl -H \"Authorization: {}\" \"{}\"",
             auth_header_value, url
@@ -218,7 +296,7 @@ impl DataApi {
         if let Some(ts) = timestamp {
             url.push_str(&format!("&timestamp={}", ts));
         }
-        let auth_header_value = format!("{}:{}", self.app_id, self.access_token);
+        let auth_header_value = self.credentials.auth_header()?;
         let curl_command = format!(
             "curl -H \"Authorization: {}\" \"{}\"",
             auth_header_value, url
@@ -260,4 +338,154 @@ impl DataApi {
 
         Ok(option_chain_response)
     }
+
+    /// # Description
+    /// Full market quotes for any number of symbols. The symbols are split into batches of
+    /// [MAX_QUOTE_SYMBOLS], the batches are fetched concurrently under the client's rate limit and
+    /// the results are merged into a map keyed by symbol.
+    ///
+    /// A failed batch or a symbol the API returned an error for does not fail the whole call,
+    /// the error is stored against each affected symbol instead.
+    ///
+    /// # Arguments
+    /// * `symbols` - Symbols for which data is to be fetched (e.g. &["NSE:SBIN-EQ", "NSE:RELIANCE-EQ"])
+    pub async fn get_market_quotes_batched(&self, symbols: &[&str]) -> HashMap<String, Result<QuoteData, FyersError>> {
+        let mut seen = HashSet::with_capacity(symbols.len());
+        let unique: Vec<&str> = symbols.iter().copied().filter(|symbol| seen.insert(*symbol)).collect();
+
+        let requests = unique.chunks(MAX_QUOTE_SYMBOLS).map(|batch| async move {
+            self.rate_limiter.acquire().await;
+            (batch, self.get_quote_batch(&batch.join(",")).await)
+        });
+
+        let mut quotes = HashMap::with_capacity(unique.len());
+        for (batch, result) in join_all(requests).await {
+            match result {
+                Ok(response) => {
+                    for entry in response.d {
+                        let symbol = entry.n.clone();
+                        quotes.insert(symbol, parse_quote_entry(entry));
+                    }
+                    for symbol in batch {
+                        if !quotes.contains_key(*symbol) {
+                            let error = FyersError::Unknown(format!("No quote returned for {}", symbol));
+                            quotes.insert(symbol.to_string(), Err(error));
+                        }
+                    }
+                }
+                Err(e) => {
+                    for symbol in batch {
+                        let error = match &e {
+                            FyersError::ApiError { s, code, message } => FyersError::ApiError {
+                                s: s.clone(),
+                                code: *code,
+                                message: message.clone(),
+                            },
+                            other => FyersError::Unknown(other.to_string()),
+                        };
+                        quotes.insert(symbol.to_string(), Err(error));
+                    }
+                }
+            }
+        }
+        quotes
+    }
+
+    // Private helper that fetches one batch of quotes, leaving the entries unparsed so that an
+    // error for one symbol does not fail the batch
+    async fn get_quote_batch(&self, symbols: &str) -> Result<RawQuoteResponse, FyersError> {
+        let url = format!("{}/quotes?symbols={}", DATA_API_BASE_URL, symbols);
+        let auth_header_value = self.credentials.auth_header()?;
+        let response = self
+            .http_client
+            .get(&url)
+            .header("Authorization", auth_header_value)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(FyersError::Network(
+                response.error_for_status().unwrap_err(),
+            ));
+        }
+
+        let quote_response: RawQuoteResponse = serde_json::from_str(&response.text().await?)?;
+        if quote_response.s != "ok" {
+            return Err(FyersError::ApiError {
+                s: quote_response.s,
+                code: quote_response.code.unwrap_or(0),
+                message: quote_response.message.unwrap_or("Error fetching quotes".to_string()),
+            });
+        }
+
+        Ok(quote_response)
+    }
+}
+
+// Parse the quote of one symbol, or the error the API returned for it
+fn parse_quote_entry(entry: RawSymbolQuote) -> Result<QuoteData, FyersError> {
+    if entry.s == "ok" {
+        return Ok(serde_json::from_value(entry.v)?);
+    }
+    Err(FyersError::ApiError {
+        s: entry.s,
+        code: entry.v.get("code").and_then(|code| code.as_i64()).unwrap_or(0),
+        message: entry
+            .v
+            .get("message")
+            .and_then(|message| message.as_str())
+            .unwrap_or("Error fetching quote")
+            .to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_bad_symbol_only_fails_its_own_quote() {
+        let response: RawQuoteResponse = serde_json::from_str(
+            r#"{"s":"ok","code":200,"d":[
+                {"n":"NSE:SBIN-EQ","s":"ok","v":{"ch":1.5,"chp":0.2,"lp":812.5,"spread":0.05,"ask":812.55,
+                 "bid":812.5,"open_price":810.0,"high_price":815.0,"low_price":808.0,"prev_close_price":811.0,
+                 "atp":812.1,"volume":123456,"short_name":"SBIN-EQ","exchange":"NSE","description":"NSE:SBIN-EQ",
+                 "original_name":"NSE:SBIN-EQ","symbol":"NSE:SBIN-EQ","fyToken":"10100000003045",
+                 "tt":"1757030400"}},
+                {"n":"NSE:NOSUCH-EQ","s":"error","v":{"code":-300,"message":"Invalid symbol"}}
+            ]}"#,
+        )
+        .unwrap();
+
+        let mut entries = response.d.into_iter().map(parse_quote_entry);
+        let quote = entries.next().unwrap().unwrap();
+        assert_eq!(quote.lp, 812.5);
+        assert_eq!(quote.tt, 1_757_030_400);
+        match entries.next().unwrap() {
+            Err(FyersError::ApiError { code, message, .. }) => {
+                assert_eq!(code, -300);
+                assert_eq!(message, "Invalid symbol");
+            }
+            other => panic!("expected an API error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn open_and_accepting_orders_are_distinct() {
+        let response: MarketStatusResponse = serde_json::from_str(
+            r#"{"s":"ok","code":200,"message":"","marketStatus":[
+                {"exchange":10,"segment":10,"market_type":"PREOPEN","status":"OPEN"},
+                {"exchange":10,"segment":10,"market_type":"NORMAL","status":"PREOPEN"},
+                {"exchange":10,"segment":11,"market_type":"NORMAL","status":"OPEN"},
+                {"exchange":11,"segment":20,"market_type":"NORMAL","status":"CLOSED"}
+            ]}"#,
+        )
+        .unwrap();
+
+        assert!(!response.is_open(10, 10));
+        assert!(response.accepts_orders(10, 10));
+        assert!(response.is_open(10, 11) && response.accepts_orders(10, 11));
+        assert!(!response.is_open(11, 20) && !response.accepts_orders(11, 20));
+        assert!(!response.accepts_orders(12, 10));
+    }
 }
@@ -18,13 +18,36 @@ pub enum FyersError {
     #[error("Auth error: {0}")]
     AuthError(String),
 
-    // Websocket errors
+    // Websocket errors. Boxed, the tungstenite error is large and would make every result
+    // carrying a FyersError large too.
     #[error("Websocket error: {0}")]
-    WebsocketError(#[from] tokio_tungstenite::tungstenite::Error),
+    WebsocketError(Box<tokio_tungstenite::tungstenite::Error>),
     #[error("Websocket error: {0}")]
     WebSocket(String),
 
+    // A request that was rejected before it was sent to the API
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+
+    // An order rejected by the pre-trade risk checks
+    #[error("Order rejected by risk check: {0}")]
+    RiskRejected(crate::risk::RiskViolation),
+
+    // A wait that did not complete in time, e.g. for an order to fill
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
+    // Errors reading or writing local files, e.g. a token store
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
     // Unknown errors
     #[error("Unknown error: {0}")]
     Unknown(String),
 }
+
+impl From<tokio_tungstenite::tungstenite::Error> for FyersError {
+    fn from(error: tokio_tungstenite::tungstenite::Error) -> Self {
+        FyersError::WebsocketError(Box::new(error))
+    }
+}
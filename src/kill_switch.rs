@@ -0,0 +1,312 @@
+use crate::broker::Broker;
+use crate::error::FyersError;
+use crate::models::orders::ModifyOrderRequest;
+use crate::models::{FundsResponse, OrdersResponse, PositionsResponse, SingleOrderRequest, SingleOrderResponse, TradesResponse};
+use crate::order_tracker::OrderState;
+use crate::risk::RiskViolation;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+/// # Description
+/// A cheap, cloneable handle that engages a [KillSwitch] from anywhere, e.g. from a
+/// [RiskManager](crate::risk::RiskManager) when the daily loss limit is reached
+#[derive(Debug, Clone, Default)]
+pub struct KillSwitchHandle {
+    engaged: Arc<AtomicBool>,
+    notify: Arc<Notify>,
+}
+
+impl KillSwitchHandle {
+    /// # Description
+    /// Block further placement. A task started with [KillSwitch::spawn_handler] also cancels all
+    /// orders and squares off all positions.
+    pub fn engage(&self) {
+        if !self.engaged.swap(true, Ordering::SeqCst) {
+            self.notify.notify_one();
+        }
+    }
+
+    pub fn is_engaged(&self) -> bool {
+        self.engaged.load(Ordering::SeqCst)
+    }
+
+    /// # Description
+    /// Allow placement again
+    pub fn reset(&self) {
+        self.engaged.store(false, Ordering::SeqCst);
+    }
+}
+
+/// What a [KillSwitch] did when it was triggered
+#[derive(Debug, Default)]
+pub struct KillSwitchReport {
+    /// Ids of the orders that were cancelled
+    pub cancelled: Vec<String>,
+    /// Orders that could not be cancelled, by id
+    pub cancel_failed: Vec<(String, FyersError)>,
+    /// Positions that were exited, as (symbol, product type, quantity sent, exit order id)
+    pub exited: Vec<(String, String, i64, String)>,
+    /// Positions that could not be exited, as (symbol, product type, error)
+    pub exit_failed: Vec<(String, String, FyersError)>,
+    /// Failures to read the order book or the positions
+    pub errors: Vec<FyersError>,
+}
+
+impl KillSwitchReport {
+    /// Whether everything that was attempted succeeded
+    pub fn is_clean(&self) -> bool {
+        self.cancel_failed.is_empty() && self.exit_failed.is_empty() && self.errors.is_empty()
+    }
+}
+
+/// # Description
+/// A [Broker] wrapper with a kill switch. Once engaged, new orders and modifications are rejected
+/// with [RiskViolation::KillSwitchEngaged], while cancellations, exits and reads still go through.
+/// [KillSwitch::trigger] engages it, cancels every pending order and exits every open position.
+/// Exits go out with [Broker::place_exit_order], so a [RiskManager](crate::risk::RiskManager)
+/// wrapped inside does not stop them.
+///
+/// Only orders sent through this wrapper are blocked. Orders placed with an
+/// [Order](crate::orders::Order) client, or through any other broker for the same account, still
+/// reach the exchange while the kill switch is engaged.
+#[derive(Debug)]
+pub struct KillSwitch<B> {
+    broker: B,
+    handle: KillSwitchHandle,
+}
+
+impl<B: Broker> KillSwitch<B> {
+    pub fn new(broker: B) -> Self {
+        Self {
+            broker,
+            handle: KillSwitchHandle::default(),
+        }
+    }
+
+    /// A handle to engage the kill switch with
+    pub fn handle(&self) -> KillSwitchHandle {
+        self.handle.clone()
+    }
+
+    pub fn broker(&self) -> &B {
+        &self.broker
+    }
+
+    pub fn is_engaged(&self) -> bool {
+        self.handle.is_engaged()
+    }
+
+    /// # Description
+    /// Stop everything: block further placement, cancel every pending order in the order book and
+    /// exit every open position with a market order, bypassing pre-trade checks. Failures do not stop the remaining steps and
+    /// are listed in the report.
+    pub async fn trigger(&self) -> KillSwitchReport {
+        self.handle.engage();
+        let mut report = KillSwitchReport::default();
+
+        // Cancel first, so that no pending order opens a new position after the exits
+        match self.broker.orders().await {
+            Ok(orders) => {
+                for order in orders.order_book.iter().filter(|order| !OrderState::of(order).is_terminal()) {
+                    match self.broker.cancel_order(&order.id).await {
+                        Ok(_) => report.cancelled.push(order.id.clone()),
+                        Err(e) => report.cancel_failed.push((order.id.clone(), e)),
+                    }
+                }
+            }
+            Err(e) => report.errors.push(e),
+        }
+
+        match self.broker.positions().await {
+            Ok(positions) => {
+                for position in positions.net_positions.iter().filter(|position| position.net_qty != 0) {
+                    let exit = SingleOrderRequest {
+                        symbol: position.symbol.clone(),
+                        qty: position.net_qty.abs(),
+                        order_type: 2,
+                        side: -position.net_qty.signum(),
+                        product_type: position.product_type.clone(),
+                        validity: "DAY".to_string(),
+                        offline_order: false,
+                        limit_price: 0.0,
+                        stop_price: 0.0,
+                        disclosed_qty: 0,
+                        stop_loss: 0.0,
+                        take_profit: 0.0,
+                        order_tag: Some("killswitch".to_string()),
+                    };
+                    match self.broker.place_exit_order(&exit).await {
+                        Ok(response) => report.exited.push((
+                            position.symbol.clone(),
+                            position.product_type.clone(),
+                            exit.qty,
+                            response.id,
+                        )),
+                        Err(e) => report.exit_failed.push((position.symbol.clone(), position.product_type.clone(), e)),
+                    }
+                }
+            }
+            Err(e) => report.errors.push(e),
+        }
+
+        report
+    }
+
+    fn check_engaged(&self) -> Result<(), FyersError> {
+        if self.handle.is_engaged() {
+            return Err(FyersError::RiskRejected(RiskViolation::KillSwitchEngaged));
+        }
+        Ok(())
+    }
+}
+
+impl<B: Broker + 'static> KillSwitch<B> {
+    /// # Description
+    /// Start a task that triggers the kill switch on Ctrl-C (and SIGTERM on unix), or when it is
+    /// engaged through a [KillSwitchHandle]. The task resolves with the report once it has
+    /// triggered.
+    pub fn spawn_handler(self: Arc<Self>) -> JoinHandle<KillSwitchReport> {
+        tokio::spawn(async move {
+            let notify = self.handle.notify.clone();
+            tokio::select! {
+                _ = shutdown_signal() => {}
+                _ = notify.notified() => {}
+            }
+            self.trigger().await
+        })
+    }
+}
+
+// Resolves on Ctrl-C, or on SIGTERM on unix
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+impl<B: Broker> Broker for KillSwitch<B> {
+    async fn place_order(&self, order: &SingleOrderRequest) -> Result<SingleOrderResponse, FyersError> {
+        self.check_engaged()?;
+        self.broker.place_order(order).await
+    }
+
+    async fn place_exit_order(&self, order: &SingleOrderRequest) -> Result<SingleOrderResponse, FyersError> {
+        self.broker.place_exit_order(order).await
+    }
+
+    async fn modify_order(&self, request: &ModifyOrderRequest) -> Result<SingleOrderResponse, FyersError> {
+        self.check_engaged()?;
+        self.broker.modify_order(request).await
+    }
+
+    async fn cancel_order(&self, id: &str) -> Result<SingleOrderResponse, FyersError> {
+        self.broker.cancel_order(id).await
+    }
+
+    async fn orders(&self) -> Result<OrdersResponse, FyersError> {
+        self.broker.orders().await
+    }
+
+    async fn positions(&self) -> Result<PositionsResponse, FyersError> {
+        self.broker.positions().await
+    }
+
+    async fn trades(&self) -> Result<TradesResponse, FyersError> {
+        self.broker.trades().await
+    }
+
+    async fn funds(&self) -> Result<FundsResponse, FyersError> {
+        self.broker.funds().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paper::PaperBroker;
+    use crate::risk::{RiskLimits, RiskManager};
+
+    fn order(symbol: &str, order_type: i64, side: i64, qty: i64, limit_price: f64) -> SingleOrderRequest {
+        SingleOrderRequest {
+            symbol: symbol.to_string(),
+            qty,
+            order_type,
+            side,
+            product_type: "INTRADAY".to_string(),
+            validity: "DAY".to_string(),
+            offline_order: false,
+            limit_price,
+            stop_price: 0.0,
+            disclosed_qty: 0,
+            stop_loss: 0.0,
+            take_profit: 0.0,
+            order_tag: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn trigger_cancels_orders_and_flattens_past_the_risk_checks() {
+        let paper = PaperBroker::new(1_000_000.0);
+        paper.on_price("NSE:SBIN-EQ", 800.0);
+        paper.on_price("NSE:TCS-EQ", 3_000.0);
+        paper.on_price("NSE:INFY-EQ", 1_500.0);
+        paper.single_order(&order("NSE:SBIN-EQ", 2, 1, 100, 0.0)).unwrap();
+        paper.single_order(&order("NSE:TCS-EQ", 2, -1, 20, 0.0)).unwrap();
+        let pending = paper.single_order(&order("NSE:INFY-EQ", 1, 1, 10, 1_400.0)).unwrap().id;
+
+        // Limits that every exit breaks: 100 and 20 are above the quantity limit, and no
+        // price is known to the risk manager
+        let limits = RiskLimits {
+            max_order_qty: Some(10),
+            price_band: Some(0.01),
+            ..Default::default()
+        };
+        let kill_switch = KillSwitch::new(RiskManager::new(paper, limits));
+        let report = kill_switch.trigger().await;
+
+        assert!(report.is_clean(), "{:?}", report);
+        assert_eq!(report.cancelled, [pending]);
+        let mut exits: Vec<(String, i64)> = report.exited.iter().map(|(symbol, _, qty, _)| (symbol.clone(), *qty)).collect();
+        exits.sort();
+        assert_eq!(exits, [("NSE:SBIN-EQ".to_string(), 100), ("NSE:TCS-EQ".to_string(), 20)]);
+
+        let positions = kill_switch.positions().await.unwrap();
+        assert!(positions.net_positions.iter().all(|position| position.net_qty == 0));
+    }
+
+    #[tokio::test]
+    async fn rejects_placement_while_engaged() {
+        let paper = PaperBroker::new(1_000_000.0);
+        paper.on_price("NSE:SBIN-EQ", 800.0);
+        let kill_switch = KillSwitch::new(paper);
+        let report = kill_switch.trigger().await;
+        assert!(report.is_clean() && report.cancelled.is_empty() && report.exited.is_empty());
+        assert!(kill_switch.is_engaged());
+
+        let placed = kill_switch.place_order(&order("NSE:SBIN-EQ", 2, 1, 1, 0.0)).await;
+        assert!(matches!(placed, Err(FyersError::RiskRejected(RiskViolation::KillSwitchEngaged))));
+        let modified = kill_switch.modify_order(&ModifyOrderRequest::new("PAPER-1")).await;
+        assert!(matches!(modified, Err(FyersError::RiskRejected(RiskViolation::KillSwitchEngaged))));
+
+        kill_switch.handle().reset();
+        assert!(kill_switch.place_order(&order("NSE:SBIN-EQ", 2, 1, 1, 0.0)).await.is_ok());
+    }
+}
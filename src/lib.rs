@@ -6,3 +6,19 @@ pub mod websocket;
 pub mod orders;
 pub mod dataapi;
 pub mod transaction;
+pub mod rate_limiter;
+pub mod options;
+pub mod token_store;
+pub mod login_server;
+pub mod accounts;
+pub mod order_tracker;
+pub mod paper;
+pub mod broker;
+pub mod charges;
+pub mod backtest;
+pub mod risk;
+pub mod kill_switch;
+pub mod pnl;
+pub mod round_trips;
+
+mod credentials;
@@ -0,0 +1,234 @@
+use crate::auth::{generate_access_token, generate_auth_url, parse_redirect_url, redirect_state_matches, LoginSession};
+use crate::error::FyersError;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use url::Url;
+
+/// How long to wait for the user to complete the login by default
+pub const DEFAULT_LOGIN_TIMEOUT: Duration = Duration::from_secs(300);
+
+// Upper bound on the size of the callback request we are willing to read
+const MAX_REQUEST_SIZE: usize = 16 * 1024;
+
+// How long a connection may take to send its request. Browsers open speculative connections
+// that never send one, and those must not hold up the real callback.
+const REQUEST_READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+const SUCCESS_PAGE: &str = "<html><body><h3>Login complete.</h3><p>You can close this window and return to the application.</p></body></html>";
+const FAILURE_PAGE: &str = "<html><body><h3>Login failed.</h3><p>Return to the application for details.</p></body></html>";
+
+/// # Description
+/// Complete the whole login flow in one call: start a local listener on the redirect URI, open the
+/// auth URL in the browser (and print it to stderr), wait for FYERS to redirect back, verify the `state`
+/// and exchange the `auth_code` for an access token.
+///
+/// The redirect URI registered for the app must point at this machine, e.g.
+/// "http://127.0.0.1:8080/callback".
+///
+/// # Arguments
+/// * `client_id` - FYERS client id
+/// * `client_secret` - FYERS client secret
+/// * `redirect_uri` - The redirect URI registered for the app
+/// * `state` - A unique, random string to prevent CSRF attacks
+/// * `timeout` - How long to wait for the user to log in
+pub async fn login_with_local_server(
+    client_id: &str,
+    client_secret: &str,
+    redirect_uri: &str,
+    state: &str,
+    timeout: Duration,
+) -> Result<String, FyersError> {
+    let listener = bind_redirect_listener(redirect_uri).await?;
+
+    let auth_url = generate_auth_url(client_id, redirect_uri, state);
+    eprintln!("Open the following URL in your browser to log in:\n\n{}\n", auth_url);
+    open_in_browser(&auth_url);
+
+    let auth_code = wait_for_auth_code(&listener, redirect_uri, state, timeout).await?;
+    generate_access_token(client_id, client_secret, &auth_code).await
+}
+
+/// # Description
+/// Same as [login_with_local_server], using the random `state` of a [LoginSession]
+///
+/// # Arguments
+/// * `session` - The login session, created with a local redirect URI
+/// * `client_secret` - FYERS client secret
+/// * `timeout` - How long to wait for the user to log in
+pub async fn login_session_with_local_server(
+    session: &LoginSession,
+    client_secret: &str,
+    timeout: Duration,
+) -> Result<String, FyersError> {
+    let listener = bind_redirect_listener(session.redirect_uri()).await?;
+
+    let auth_url = session.auth_url();
+    eprintln!("Open the following URL in your browser to log in:\n\n{}\n", auth_url);
+    open_in_browser(&auth_url);
+
+    let auth_code = wait_for_auth_code(&listener, session.redirect_uri(), session.state(), timeout).await?;
+    generate_access_token(session.client_id(), client_secret, &auth_code).await
+}
+
+/// # Description
+/// Wait for FYERS to redirect the browser to a local redirect URI and return the `auth_code`
+/// from the callback, after verifying that its `state` matches the one sent with the auth URL.
+/// Requests without the expected `state` are answered with an error page and the wait goes on
+/// until `timeout`.
+///
+/// # Arguments
+/// * `redirect_uri` - The redirect URI registered for the app, e.g. "http://127.0.0.1:8080/callback"
+/// * `state` - The state sent with the auth URL
+/// * `timeout` - How long to wait for the callback
+pub async fn capture_auth_code(redirect_uri: &str, state: &str, timeout: Duration) -> Result<String, FyersError> {
+    let listener = bind_redirect_listener(redirect_uri).await?;
+    wait_for_auth_code(&listener, redirect_uri, state, timeout).await
+}
+
+async fn bind_redirect_listener(redirect_uri: &str) -> Result<TcpListener, FyersError> {
+    let url = Url::parse(redirect_uri)
+        .map_err(|e| FyersError::AuthError(format!("Invalid redirect URI {}: {}", redirect_uri, e)))?;
+    if url.scheme() != "http" {
+        return Err(FyersError::AuthError(
+            "The local login server only supports http:// redirect URIs".to_string(),
+        ));
+    }
+    let host = url.host_str().unwrap_or("127.0.0.1");
+    let port = url.port_or_known_default().unwrap_or(80);
+    Ok(TcpListener::bind((host, port)).await?)
+}
+
+async fn wait_for_auth_code(
+    listener: &TcpListener,
+    redirect_uri: &str,
+    state: &str,
+    timeout: Duration,
+) -> Result<String, FyersError> {
+    let expected_path = Url::parse(redirect_uri)
+        .map(|url| url.path().to_string())
+        .unwrap_or_else(|_| "/".to_string());
+
+    let wait = async {
+        loop {
+            let (mut stream, _) = listener.accept().await?;
+            let target = match tokio::time::timeout(REQUEST_READ_TIMEOUT, read_request_target(&mut stream)).await {
+                Ok(Ok(Some(target))) => target,
+                _ => continue,
+            };
+
+            // The target is relative, so give it a base to parse against
+            let callback = match Url::parse(&format!("http://localhost{}", target)) {
+                Ok(callback) if callback.path() == expected_path => callback,
+                _ => {
+                    // Browsers also ask for things like /favicon.ico
+                    respond(&mut stream, "404 Not Found", "").await;
+                    continue;
+                }
+            };
+
+            // Requests without our state are not the login redirect, keep waiting for it
+            if !redirect_state_matches(&callback, state) {
+                respond(&mut stream, "400 Bad Request", FAILURE_PAGE).await;
+                continue;
+            }
+
+            let result = parse_redirect_url(&callback, state);
+            let (status, page) = if result.is_ok() { ("200 OK", SUCCESS_PAGE) } else { ("400 Bad Request", FAILURE_PAGE) };
+            respond(&mut stream, status, page).await;
+            return result;
+        }
+    };
+
+    tokio::time::timeout(timeout, wait)
+        .await
+        .map_err(|_| FyersError::AuthError("Timed out waiting for the login redirect".to_string()))?
+}
+
+/// Read an HTTP request head and return the request target of a GET request
+async fn read_request_target(stream: &mut TcpStream) -> Result<Option<String>, FyersError> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !buffer.windows(4).any(|w| w == b"\r\n\r\n") && buffer.len() < MAX_REQUEST_SIZE {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+
+    let head = String::from_utf8_lossy(&buffer);
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some(target)) => Ok(Some(target.to_string())),
+        _ => Ok(None),
+    }
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, body: &str) {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    // The browser going away is not an error for the login flow
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// Best effort attempt to open a URL in the default browser
+fn open_in_browser(url: &str) {
+    let result = if cfg!(target_os = "macos") {
+        std::process::Command::new("open").arg(url).spawn()
+    } else if cfg!(target_os = "windows") {
+        // Not `cmd /C start`, which would treat the `&` between the query parameters as a
+        // command separator
+        std::process::Command::new("rundll32").args(["url.dll,FileProtocolHandler", url]).spawn()
+    } else {
+        std::process::Command::new("xdg-open").arg(url).spawn()
+    };
+    if result.is_err() {
+        eprintln!("Could not open a browser, please open the URL above manually.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn get(port: u16, target: &str) -> String {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let request = format!("GET {} HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n", target);
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response.lines().next().unwrap_or_default().to_string()
+    }
+
+    #[tokio::test]
+    async fn waits_past_stray_requests_for_the_callback() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let redirect_uri = format!("http://127.0.0.1:{}/callback", port);
+        let server = tokio::spawn(async move {
+            wait_for_auth_code(&listener, &redirect_uri, "expected", Duration::from_secs(30)).await
+        });
+
+        // A speculative connection that never sends a request
+        let _idle = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        assert_eq!(get(port, "/favicon.ico").await, "HTTP/1.1 404 Not Found");
+        assert_eq!(get(port, "/callback?s=ok&auth_code=forged&state=wrong").await, "HTTP/1.1 400 Bad Request");
+        assert_eq!(get(port, "/callback?s=error&message=denied").await, "HTTP/1.1 400 Bad Request");
+        assert_eq!(get(port, "/callback?s=ok&code=200&auth_code=abc&state=expected").await, "HTTP/1.1 200 OK");
+
+        assert_eq!(server.await.unwrap().unwrap(), "abc");
+    }
+
+    #[tokio::test]
+    async fn times_out_without_a_callback() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let result = wait_for_auth_code(&listener, "http://127.0.0.1/callback", "expected", Duration::from_millis(50)).await;
+        assert!(matches!(result, Err(FyersError::AuthError(_))));
+    }
+}
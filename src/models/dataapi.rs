@@ -4,7 +4,7 @@ use serde::{Serialize, Deserialize};
 use serde_with::{ serde_as, DisplayFromStr };
 
 /// A single candle
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
 pub struct Candle(
     pub i64,
     pub f64,
@@ -87,6 +87,28 @@ pub struct SymbolQuoteResponse {
     pub v: QuoteData,
 }
 
+/// Quote entry of a batched quote request, parsed leniently: an entry the API returned an error
+/// for only carries `code` and `message`, not the fields of [QuoteData]
+#[derive(Debug, Deserialize)]
+pub(crate) struct RawSymbolQuote {
+    pub s: String,
+    pub n: String,
+    #[serde(default)]
+    pub v: serde_json::Value,
+}
+
+/// Top level response of a batched quote request
+#[derive(Debug, Deserialize)]
+pub(crate) struct RawQuoteResponse {
+    pub s: String,
+    #[serde(default)]
+    pub d: Vec<RawSymbolQuote>,
+    #[serde(default)]
+    pub code: Option<i64>,
+    #[serde(default)]
+    pub message: Option<String>
+}
+
 /// Top level response for a quote request
 #[derive(Debug, Deserialize, Serialize)]
 pub struct QuoteResponse {
@@ -251,3 +273,159 @@ pub struct OptionChainResponse {
     #[serde(default)]
     pub message: Option<String>
 }
+
+///////////////////
+// Market Status //
+///////////////////
+
+/// The session a market status entry is for
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(from = "String", into = "String")]
+pub enum MarketType {
+    Normal,
+    PreOpen,
+    PostClose,
+    Muhurat,
+    OddLot,
+    CallAuction,
+    /// A market type not known to this crate, as returned by the API
+    Other(String),
+}
+
+impl From<String> for MarketType {
+    fn from(value: String) -> Self {
+        match value.to_ascii_uppercase().as_str() {
+            "NORMAL" => MarketType::Normal,
+            "PREOPEN" | "PRE_OPEN" => MarketType::PreOpen,
+            "POSTCLOSE" | "POST_CLOSE" => MarketType::PostClose,
+            "MUHURAT" | "MUHURTA" | "SPECIAL" => MarketType::Muhurat,
+            "ODD_LOT" | "ODDLOT" => MarketType::OddLot,
+            "CALL_AUCTION" | "CALL_AUCTION1" | "CALL_AUCTION2" | "AUCTION" => MarketType::CallAuction,
+            _ => MarketType::Other(value),
+        }
+    }
+}
+
+impl From<MarketType> for String {
+    fn from(value: MarketType) -> Self {
+        match value {
+            MarketType::Normal => "NORMAL".to_string(),
+            MarketType::PreOpen => "PREOPEN".to_string(),
+            MarketType::PostClose => "POSTCLOSE".to_string(),
+            MarketType::Muhurat => "MUHURAT".to_string(),
+            MarketType::OddLot => "ODD_LOT".to_string(),
+            MarketType::CallAuction => "CALL_AUCTION".to_string(),
+            MarketType::Other(value) => value,
+        }
+    }
+}
+
+/// Whether a market is open
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(from = "String", into = "String")]
+pub enum MarketState {
+    Open,
+    Closed,
+    PreOpen,
+    PreOpenClosed,
+    PostCloseStart,
+    PostCloseClosed,
+    /// A status not known to this crate, as returned by the API
+    Other(String),
+}
+
+impl From<String> for MarketState {
+    fn from(value: String) -> Self {
+        match value.to_ascii_uppercase().as_str() {
+            "OPEN" => MarketState::Open,
+            "CLOSED" | "CLOSE" => MarketState::Closed,
+            "PREOPEN" | "PRE_OPEN" => MarketState::PreOpen,
+            "PREOPEN_CLOSED" => MarketState::PreOpenClosed,
+            "POSTCLOSE_START" => MarketState::PostCloseStart,
+            "POSTCLOSE_CLOSED" => MarketState::PostCloseClosed,
+            _ => MarketState::Other(value),
+        }
+    }
+}
+
+impl From<MarketState> for String {
+    fn from(value: MarketState) -> Self {
+        match value {
+            MarketState::Open => "OPEN".to_string(),
+            MarketState::Closed => "CLOSED".to_string(),
+            MarketState::PreOpen => "PREOPEN".to_string(),
+            MarketState::PreOpenClosed => "PREOPEN_CLOSED".to_string(),
+            MarketState::PostCloseStart => "POSTCLOSE_START".to_string(),
+            MarketState::PostCloseClosed => "POSTCLOSE_CLOSED".to_string(),
+            MarketState::Other(value) => value,
+        }
+    }
+}
+
+/// The status of one market type of an exchange segment
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct MarketStatus {
+    /// 10: NSE, 11: MCX, 12: BSE
+    pub exchange: i64,
+    /// 10: capital market, 11: equity derivatives, 12: currency derivatives, 20: commodity
+    pub segment: i64,
+    pub market_type: MarketType,
+    pub status: MarketState,
+}
+
+impl MarketStatus {
+    /// Whether the session is in continuous trading
+    pub fn is_open(&self) -> bool {
+        self.status == MarketState::Open
+    }
+
+    /// Whether the session takes orders right now, in continuous trading or in the pre-open and
+    /// post-close order entry
+    pub fn accepts_orders(&self) -> bool {
+        matches!(self.status, MarketState::Open | MarketState::PreOpen | MarketState::PostCloseStart)
+    }
+}
+
+/// Top level response for a market status request
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketStatusResponse {
+    pub s: String,
+    #[serde(default)]
+    pub code: i64,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub market_status: Vec<MarketStatus>,
+}
+
+impl MarketStatusResponse {
+    /// # Description
+    /// The status of a market type of an exchange segment
+    ///
+    /// # Arguments
+    /// * `exchange` - 10: NSE, 11: MCX, 12: BSE
+    /// * `segment` - 10: capital market, 11: equity derivatives, 12: currency derivatives, 20: commodity
+    /// * `market_type` - The session, e.g. [MarketType::Normal]
+    pub fn get(&self, exchange: i64, segment: i64, market_type: &MarketType) -> Option<&MarketStatus> {
+        self.market_status
+            .iter()
+            .find(|status| status.exchange == exchange && status.segment == segment && &status.market_type == market_type)
+    }
+
+    /// # Description
+    /// Whether the normal session of an exchange segment is in continuous trading, see
+    /// [MarketStatus::is_open]
+    pub fn is_open(&self, exchange: i64, segment: i64) -> bool {
+        self.get(exchange, segment, &MarketType::Normal)
+            .is_some_and(MarketStatus::is_open)
+    }
+
+    /// # Description
+    /// Whether the normal session of an exchange segment takes orders, see
+    /// [MarketStatus::accepts_orders]
+    pub fn accepts_orders(&self, exchange: i64, segment: i64) -> bool {
+        self.get(exchange, segment, &MarketType::Normal)
+            .is_some_and(MarketStatus::accepts_orders)
+    }
+}
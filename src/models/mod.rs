@@ -11,21 +11,41 @@ pub mod websocket;
 pub use user::Profile;
 pub use user::ProfileResponse;
 pub use user::FundsResponse;
+pub use user::Funds;
+pub use user::SegmentFunds;
 pub use user::HoldingsResponse;
+pub use user::LogoutResponse;
 
 pub use dataapi::HistoryResponse;
 pub use dataapi::QuoteResponse;
 pub use dataapi::MarketDepthResponse;
 pub use dataapi::OptionChainResponse;
 pub use dataapi::Candle;
+pub use dataapi::MarketStatusResponse;
 
 pub use transaction::OrdersResponse;
 pub use transaction::PositionsResponse;
 pub use transaction::TradesResponse;
+pub use transaction::Order;
+pub use transaction::Trade;
 
 pub use orders::SingleOrderRequest;
 pub use orders::SingleOrderResponse;
 pub use orders::MultipleOrdersResponse;
+pub use orders::ModifyOrderRequest;
+pub use orders::GttLeg;
+pub use orders::GttOrderInfo;
+pub use orders::GttOrderRequest;
+pub use orders::GttModifyRequest;
+pub use orders::GttOrderResponse;
+pub use orders::GttOrdersResponse;
+pub use orders::OrderLeg;
+pub use orders::MultiLegOrderRequest;
+pub use orders::MultiLegOrderResponse;
+pub use orders::MultiLegOrderResult;
+pub use orders::MarginLeg;
+pub use orders::MarginResponse;
+pub use orders::BasketMargin;
 
 pub use market_data::fyers_v1;
 
@@ -65,3 +65,498 @@ pub struct MultipleOrdersResponse {
     pub data: Vec<Data>,
     pub message: String,
 }
+
+/// Request structure for modifying a pending order. Fields left as `None` are not changed.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModifyOrderRequest {
+    pub id: String,
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub order_type: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit_price: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_price: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qty: Option<i64>,
+}
+
+impl ModifyOrderRequest {
+    /// # Description
+    /// A modification of the order with the given id that changes nothing yet
+    pub fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Request structure for cancelling a pending order
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CancelOrderRequest {
+    pub id: String,
+}
+
+/////////
+// GTT //
+/////////
+
+/// A single leg of a GTT order
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GttLeg {
+    pub price: f64,
+    pub trigger_price: f64,
+    pub qty: i64,
+}
+
+impl GttLeg {
+    pub fn new(price: f64, trigger_price: f64, qty: i64) -> Self {
+        Self { price, trigger_price, qty }
+    }
+}
+
+/// The legs of a GTT order. `leg2` is only set for OCO orders.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GttOrderInfo {
+    pub leg1: GttLeg,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leg2: Option<GttLeg>,
+}
+
+/// Request structure for placing a GTT order
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GttOrderRequest {
+    pub side: i64,
+
+        //1 => Buy
+        //-1 => Sell
+
+    pub symbol: String,
+    pub product_type: String,
+
+        //CNC => For equity only
+        //MARGIN => Applicable only for derivatives
+        //MTF => Approved Symbols Only
+
+    pub order_info: GttOrderInfo,
+}
+
+impl GttOrderRequest {
+    /// # Description
+    /// A single GTT order, placed when the trigger price is hit
+    ///
+    /// # Arguments
+    /// * `symbol` - The symbol to trade, e.g. "NSE:SBIN-EQ"
+    /// * `side` - 1 for buy, -1 for sell
+    /// * `product_type` - "CNC", "MARGIN" or "MTF"
+    /// * `leg` - The trigger price, limit price and quantity
+    pub fn single(symbol: &str, side: i64, product_type: &str, leg: GttLeg) -> Self {
+        Self {
+            side,
+            symbol: symbol.to_string(),
+            product_type: product_type.to_string(),
+            order_info: GttOrderInfo { leg1: leg, leg2: None },
+        }
+    }
+
+    /// # Description
+    /// A one-cancels-other GTT order, e.g. a target and a stop loss for an open position. When
+    /// one leg triggers the other is cancelled.
+    ///
+    /// # Arguments
+    /// * `symbol` - The symbol to trade, e.g. "NSE:SBIN-EQ"
+    /// * `side` - 1 for buy, -1 for sell
+    /// * `product_type` - "CNC", "MARGIN" or "MTF"
+    /// * `leg1` - The first leg (usually the target)
+    /// * `leg2` - The second leg (usually the stop loss)
+    pub fn oco(symbol: &str, side: i64, product_type: &str, leg1: GttLeg, leg2: GttLeg) -> Self {
+        Self {
+            side,
+            symbol: symbol.to_string(),
+            product_type: product_type.to_string(),
+            order_info: GttOrderInfo { leg1, leg2: Some(leg2) },
+        }
+    }
+
+    /// # Description
+    /// A single GTT order that uses the symbol, side, product type, quantity and limit price of
+    /// a regular order
+    ///
+    /// # Arguments
+    /// * `order` - The regular order to take the fields from
+    /// * `trigger_price` - The price at which the order is placed
+    pub fn from_order(order: &SingleOrderRequest, trigger_price: f64) -> Self {
+        Self::single(
+            &order.symbol,
+            order.side,
+            &order.product_type,
+            GttLeg::new(order.limit_price, trigger_price, order.qty),
+        )
+    }
+}
+
+/// Request structure for modifying a GTT order
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GttModifyRequest {
+    pub id: String,
+    pub order_info: GttOrderInfo,
+}
+
+/// Request structure for cancelling a GTT order
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GttCancelRequest {
+    pub id: String,
+}
+
+/// Response structure for placing, modifying or cancelling a GTT order
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GttOrderResponse {
+    pub s: String,
+    pub code: i64,
+    pub message: String,
+    #[serde(default)]
+    pub id: String,
+}
+
+/// A single GTT order entry
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GttOrder {
+    pub id: String,
+    #[serde(default)]
+    pub id_fyers: String,
+    pub symbol: String,
+    #[serde(default)]
+    pub symbol_desc: String,
+    #[serde(default)]
+    pub fy_token: String,
+    #[serde(rename = "clientId", default)]
+    pub client_id: String,
+    #[serde(default)]
+    pub exchange: i64,
+    #[serde(default)]
+    pub segment: i64,
+    #[serde(default)]
+    pub instrument: i64,
+    pub product_type: String,
+    pub tran_side: i64, // 1: buy, -1: sell
+    pub ord_status: i64,
+    #[serde(default)]
+    pub gtt_oco_ind: i64, // 1: single, 2: OCO
+    pub qty: i64,
+    pub price_limit: f64,
+    pub price_trigger: f64,
+    #[serde(default)]
+    pub qty2: i64,
+    #[serde(default)]
+    pub price2_limit: f64,
+    #[serde(default)]
+    pub price2_trigger: f64,
+    #[serde(default)]
+    pub lot_size: i64,
+    #[serde(default)]
+    pub ltp: f64,
+    #[serde(default)]
+    pub create_time: String,
+    #[serde(default)]
+    pub oms_msg: String,
+}
+
+/// Top level response for the /gtt/orders endpoint
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GttOrdersResponse {
+    pub s: String,
+    pub code: i64,
+    pub message: String,
+    #[serde(default)]
+    pub order_book: Vec<GttOrder>,
+}
+
+///////////////
+// Multi-leg //
+///////////////
+
+/// A single leg of a multi-leg order
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderLeg {
+    pub symbol: String,
+    pub qty: i64,
+    pub side: i64,
+
+        //1 => Buy
+        //-1 => Sell
+
+    #[serde(rename = "type")]
+    pub order_type: i64,
+
+        //1 => Limit Order (the only type multi-leg orders accept)
+
+    pub limit_price: f64,
+}
+
+impl OrderLeg {
+    /// # Description
+    /// A limit order leg
+    ///
+    /// # Arguments
+    /// * `symbol` - The contract to trade, e.g. "NSE:NIFTY24JUN23000CE"
+    /// * `qty` - The quantity, in multiples of the lot size
+    /// * `side` - 1 for buy, -1 for sell
+    /// * `limit_price` - The limit price of the leg
+    pub fn limit(symbol: &str, qty: i64, side: i64, limit_price: f64) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            qty,
+            side,
+            order_type: 1,
+            limit_price,
+        }
+    }
+}
+
+/// The legs of a multi-leg order. `leg3` is only set for 3L orders.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MultiLegLegs {
+    pub leg1: OrderLeg,
+    pub leg2: OrderLeg,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leg3: Option<OrderLeg>,
+}
+
+impl MultiLegLegs {
+    /// The legs in order, with their names
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &OrderLeg)> {
+        [("leg1", Some(&self.leg1)), ("leg2", Some(&self.leg2)), ("leg3", self.leg3.as_ref())]
+            .into_iter()
+            .filter_map(|(name, leg)| leg.map(|leg| (name, leg)))
+    }
+}
+
+/// Request structure for a multi-leg order. All legs are sent to the exchange together, so
+/// either every leg is placed or none is.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiLegOrderRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_tag: Option<String>,
+    pub product_type: String,
+
+        //INTRADAY => Applicable for all segments.
+        //MARGIN => Applicable only for derivatives
+
+    pub offline_order: bool,
+    pub order_type: String,
+
+        //2L => Two legs
+        //3L => Three legs
+
+    pub validity: String,
+
+        //IOC => The only validity multi-leg orders accept
+
+    pub legs: MultiLegLegs,
+}
+
+impl MultiLegOrderRequest {
+    /// # Description
+    /// A two leg order, e.g. a straddle, strangle or vertical spread
+    ///
+    /// # Arguments
+    /// * `product_type` - "INTRADAY" or "MARGIN"
+    /// * `leg1` - The first leg
+    /// * `leg2` - The second leg
+    pub fn two_leg(product_type: &str, leg1: OrderLeg, leg2: OrderLeg) -> Self {
+        Self {
+            order_tag: None,
+            product_type: product_type.to_string(),
+            offline_order: false,
+            order_type: "2L".to_string(),
+            validity: "IOC".to_string(),
+            legs: MultiLegLegs { leg1, leg2, leg3: None },
+        }
+    }
+
+    /// # Description
+    /// A three leg order, e.g. a ratio spread
+    ///
+    /// # Arguments
+    /// * `product_type` - "INTRADAY" or "MARGIN"
+    /// * `leg1` - The first leg
+    /// * `leg2` - The second leg
+    /// * `leg3` - The third leg
+    pub fn three_leg(product_type: &str, leg1: OrderLeg, leg2: OrderLeg, leg3: OrderLeg) -> Self {
+        Self {
+            order_tag: None,
+            product_type: product_type.to_string(),
+            offline_order: false,
+            order_type: "3L".to_string(),
+            validity: "IOC".to_string(),
+            legs: MultiLegLegs { leg1, leg2, leg3: Some(leg3) },
+        }
+    }
+
+    pub fn with_order_tag(mut self, order_tag: &str) -> Self {
+        self.order_tag = Some(order_tag.to_string());
+        self
+    }
+
+    /// # Description
+    /// Check the request against the rules of the multi-leg endpoint: the order type matches
+    /// the number of legs, the product type is INTRADAY or MARGIN, the validity is IOC and every
+    /// leg is a limit order with a price.
+    pub fn validate(&self) -> Result<(), String> {
+        let expected_type = if self.legs.leg3.is_some() { "3L" } else { "2L" };
+        if self.order_type != expected_type {
+            return Err(format!("order type {} does not match the number of legs ({})", self.order_type, expected_type));
+        }
+        if !matches!(self.product_type.as_str(), "INTRADAY" | "MARGIN") {
+            return Err(format!("multi-leg orders must be INTRADAY or MARGIN, got {}", self.product_type));
+        }
+        if self.validity != "IOC" {
+            return Err(format!("multi-leg orders must have IOC validity, got {}", self.validity));
+        }
+        for (name, leg) in self.legs.iter() {
+            if leg.order_type != 1 {
+                return Err(format!("{} must be a limit order", name));
+            }
+            if leg.limit_price <= 0.0 {
+                return Err(format!("{} must have a limit price", name));
+            }
+            if leg.qty <= 0 {
+                return Err(format!("{} must have a positive quantity", name));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Response structure for a multi-leg order
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MultiLegOrderResponse {
+    pub s: String,
+    pub code: i64,
+    pub message: String,
+    #[serde(default)]
+    pub id: String,
+}
+
+/// The status of a single leg of a multi-leg order, from the order book
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LegStatus {
+    pub leg: String,
+    pub symbol: String,
+    /// The id of the leg's order, if it was found in the order book
+    pub order_id: Option<String>,
+    /// The order status, as in the order book (2: traded, 1: cancelled, 5: rejected, 6: pending, ...)
+    pub status: Option<i64>,
+    pub filled_qty: i64,
+    pub traded_price: f64,
+    pub message: String,
+}
+
+/// The result of placing a multi-leg order: the API response and the status of every leg
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MultiLegOrderResult {
+    pub response: MultiLegOrderResponse,
+    pub legs: Vec<LegStatus>,
+}
+
+////////////
+// Margin //
+////////////
+
+/// A leg of a margin calculation
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarginLeg {
+    pub symbol: String,
+    pub qty: i64,
+    pub side: i64,
+    #[serde(rename = "type")]
+    pub order_type: i64,
+    pub product_type: String,
+    pub limit_price: f64,
+    pub stop_price: f64,
+    pub stop_loss: f64,
+    pub take_profit: f64,
+}
+
+impl From<&SingleOrderRequest> for MarginLeg {
+    fn from(order: &SingleOrderRequest) -> Self {
+        Self {
+            symbol: order.symbol.clone(),
+            qty: order.qty,
+            side: order.side,
+            order_type: order.order_type,
+            product_type: order.product_type.clone(),
+            limit_price: order.limit_price,
+            stop_price: order.stop_price,
+            stop_loss: order.stop_loss,
+            take_profit: order.take_profit,
+        }
+    }
+}
+
+/// Request structure for the /multiorder/margin endpoint
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MarginRequest {
+    pub data: Vec<MarginLeg>,
+}
+
+/// The margins returned by the /multiorder/margin endpoint
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct MarginData {
+    /// Margin available in the account
+    #[serde(default)]
+    pub margin_avail: f64,
+    /// Total margin required, including the margin already blocked by open positions
+    #[serde(default)]
+    pub margin_total: f64,
+    /// Margin required by the new orders
+    #[serde(default)]
+    pub margin_new_order: f64,
+}
+
+/// Top level response for the /multiorder/margin endpoint
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MarginResponse {
+    pub s: String,
+    pub code: i64,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub data: MarginData,
+}
+
+/// The margin of a basket of orders, with the benefit of the legs hedging each other
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BasketMargin {
+    /// Margin required by the basket as a whole
+    pub total_margin: f64,
+    /// Margin available in the account
+    pub available_margin: f64,
+    /// Margin required by every leg on its own, in the order of the legs
+    pub leg_margins: Vec<f64>,
+    /// Sum of the margins of the legs on their own
+    pub standalone_margin: f64,
+    /// Margin saved because the legs hedge each other (`standalone_margin - total_margin`).
+    /// Negative when the basket needs more margin than its legs on their own.
+    pub margin_benefit: f64,
+}
+
+impl BasketMargin {
+    /// Whether the available margin covers the basket
+    pub fn is_sufficient(&self) -> bool {
+        self.available_margin >= self.total_margin
+    }
+
+    /// Margin missing to place the basket, 0 when it is covered
+    pub fn shortfall(&self) -> f64 {
+        (self.total_margin - self.available_margin).max(0.0)
+    }
+}
@@ -5,7 +5,7 @@ use serde::{Serialize, Deserialize};
 ////////////
 
 /// A single order entry
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Order {
     pub id: String,
@@ -116,7 +116,7 @@ pub struct PositionsResponse {
 ////////////
 
 /// A single trade item for the tradeBook array
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Trade {
     pub symbol: String,
@@ -153,3 +153,39 @@ pub struct TradesResponse {
     pub message: String,
     pub trade_book: Vec<Trade>,
 }
+
+/// An order book entry for tests, with every field not given left empty
+#[cfg(test)]
+pub(crate) fn test_order(id: &str, symbol: &str, side: i64, qty: i64, status: i64) -> Order {
+    Order {
+        id: id.to_string(),
+        exch_ord_id: String::new(),
+        symbol: symbol.to_string(),
+        qty,
+        remaining_quantity: qty,
+        filled_qty: 0,
+        status,
+        sl_no: 0,
+        message: String::new(),
+        segment: 11,
+        limit_price: 0.0,
+        stop_price: 0.0,
+        product_type: "INTRADAY".to_string(),
+        in_type: 1,
+        side,
+        disclosed_qty: 0,
+        order_validity: "DAY".to_string(),
+        order_date_time: "05-Sep-2025 09:15:00".to_string(),
+        parent_id: String::new(),
+        traded_price: 0.0,
+        source: String::new(),
+        fytoken: String::new(),
+        offline_order: false,
+        pan: String::new(),
+        client_id: String::new(),
+        exchange: 10,
+        instrument: 0,
+        disclose_qty: 0,
+        order_tag: String::new(),
+    }
+}
@@ -28,6 +28,14 @@ pub struct Profile {
     pub mtf_enabled: bool,
 }
 
+/// Top level response for the /logout endpoint
+#[derive(Debug, Deserialize)]
+pub struct LogoutResponse {
+    pub s: String,
+    pub code: i64,
+    pub message: String,
+}
+
 ///////////
 // Funds //
 ///////////
@@ -51,6 +59,109 @@ pub struct FundsResponse {
     pub fund_limit: Vec<FundLimit>,
 }
 
+impl FundsResponse {
+    /// # Description
+    /// The fund limits as a typed [Funds] view
+    pub fn funds(&self) -> Funds {
+        Funds::from(self)
+    }
+}
+
+/// The fund limits of one segment, by name instead of by `id`/`title`. Entries missing from
+/// the response are 0.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SegmentFunds {
+    pub total_balance: f64,
+    /// Margin blocked by open positions and pending orders
+    pub utilized_margin: f64,
+    pub clear_balance: f64,
+    pub realized_pl: f64,
+    /// Collateral margin, e.g. from pledged holdings
+    pub collaterals: f64,
+    /// Funds transferred in (or out) during the day
+    pub payin: f64,
+    pub receivables: f64,
+    pub adhoc_limits: f64,
+    pub limit_at_start_of_day: f64,
+    pub available_balance: f64,
+}
+
+impl SegmentFunds {
+    // Set the field of a fund limit entry, known by its id or else by its title
+    fn set(&mut self, id: i64, title: &str, amount: f64) {
+        let field = match (id, title.to_ascii_lowercase().as_str()) {
+            (1, _) | (_, "total balance") => &mut self.total_balance,
+            (2, _) | (_, "utilized amount") => &mut self.utilized_margin,
+            (3, _) | (_, "clear balance") => &mut self.clear_balance,
+            (4, _) | (_, "realized profit and loss") => &mut self.realized_pl,
+            (5, _) | (_, "collaterals") => &mut self.collaterals,
+            (6, _) | (_, "fund transfer") => &mut self.payin,
+            (7, _) | (_, "receivables") => &mut self.receivables,
+            (8, _) | (_, "adhoc limits") => &mut self.adhoc_limits,
+            (9, _) | (_, "limit at start of the day") => &mut self.limit_at_start_of_day,
+            (10, _) | (_, "available balance") => &mut self.available_balance,
+            _ => return,
+        };
+        *field = amount;
+    }
+}
+
+/// # Description
+/// A typed view of [FundsResponse], per segment
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Funds {
+    pub equity: SegmentFunds,
+    pub commodity: SegmentFunds,
+}
+
+impl Funds {
+    /// Available balance across the equity and commodity segments
+    pub fn total_available(&self) -> f64 {
+        self.equity.available_balance + self.commodity.available_balance
+    }
+}
+
+impl std::ops::Add for SegmentFunds {
+    type Output = SegmentFunds;
+
+    fn add(self, other: SegmentFunds) -> SegmentFunds {
+        SegmentFunds {
+            total_balance: self.total_balance + other.total_balance,
+            utilized_margin: self.utilized_margin + other.utilized_margin,
+            clear_balance: self.clear_balance + other.clear_balance,
+            realized_pl: self.realized_pl + other.realized_pl,
+            collaterals: self.collaterals + other.collaterals,
+            payin: self.payin + other.payin,
+            receivables: self.receivables + other.receivables,
+            adhoc_limits: self.adhoc_limits + other.adhoc_limits,
+            limit_at_start_of_day: self.limit_at_start_of_day + other.limit_at_start_of_day,
+            available_balance: self.available_balance + other.available_balance,
+        }
+    }
+}
+
+impl std::ops::Add for Funds {
+    type Output = Funds;
+
+    fn add(self, other: Funds) -> Funds {
+        Funds {
+            equity: self.equity + other.equity,
+            commodity: self.commodity + other.commodity,
+        }
+    }
+}
+
+impl From<&FundsResponse> for Funds {
+    fn from(response: &FundsResponse) -> Self {
+        let mut funds = Funds::default();
+        for limit in &response.fund_limit {
+            funds.equity.set(limit.id, &limit.title, limit.equity_amount);
+            funds.commodity.set(limit.id, &limit.title, limit.commodity_amount);
+        }
+        funds
+    }
+}
+
 //////////////
 // Holdings //
 //////////////
@@ -0,0 +1,338 @@
+use std::collections::BTreeMap;
+
+use crate::models::dataapi::{ChainData, ChainEntry, OptionData, UnderlyingData};
+
+/// Open interest change classification for a single contract, derived from the direction of its
+/// price change and open interest change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OiBuildup {
+    /// Price up, OI up
+    LongBuildup,
+    /// Price down, OI up
+    ShortBuildup,
+    /// Price up, OI down
+    ShortCovering,
+    /// Price down, OI down
+    LongUnwinding,
+    /// Either price or OI did not change
+    Neutral,
+}
+
+impl OiBuildup {
+    /// # Description
+    /// Classify a contract from its price change and OI change
+    ///
+    /// # Arguments
+    /// * `price_change` - Change in LTP (e.g. `ltpch`)
+    /// * `oi_change` - Change in open interest (e.g. `oich`)
+    pub fn classify(price_change: f64, oi_change: i64) -> Self {
+        match (price_change.partial_cmp(&0.0), oi_change.signum()) {
+            (Some(std::cmp::Ordering::Greater), 1) => OiBuildup::LongBuildup,
+            (Some(std::cmp::Ordering::Less), 1) => OiBuildup::ShortBuildup,
+            (Some(std::cmp::Ordering::Greater), -1) => OiBuildup::ShortCovering,
+            (Some(std::cmp::Ordering::Less), -1) => OiBuildup::LongUnwinding,
+            _ => OiBuildup::Neutral,
+        }
+    }
+
+    /// Classify an option contract from the chain
+    pub fn of(option: &OptionData) -> Self {
+        Self::classify(option.ltpch, option.oich)
+    }
+}
+
+/// The call and put contracts at a single strike
+#[derive(Debug, Clone, Copy)]
+pub struct StrikePair<'a> {
+    pub strike: f64,
+    pub call: Option<&'a OptionData>,
+    pub put: Option<&'a OptionData>,
+}
+
+impl StrikePair<'_> {
+    pub fn call_oi(&self) -> i64 {
+        self.call.map_or(0, |c| c.oi)
+    }
+    pub fn put_oi(&self) -> i64 {
+        self.put.map_or(0, |p| p.oi)
+    }
+    /// Put OI divided by call OI at this strike, if there is any call OI
+    pub fn pcr(&self) -> Option<f64> {
+        ratio(self.put_oi(), self.call_oi())
+    }
+}
+
+/// Analytics computed on top of an option chain returned by
+/// [DataApi::get_option_chain](crate::dataapi::DataApi::get_option_chain)
+#[derive(Debug)]
+pub struct ChainAnalytics<'a> {
+    data: &'a ChainData,
+    underlying: Option<&'a UnderlyingData>,
+    strikes: Vec<StrikePair<'a>>,
+}
+
+impl<'a> ChainAnalytics<'a> {
+    /// # Description
+    /// Pair up the calls and puts of an option chain by strike
+    ///
+    /// # Arguments
+    /// * `data` - The `data` payload of an `OptionChainResponse`
+    pub fn new(data: &'a ChainData) -> Self {
+        let mut underlying = None;
+        // Strikes are keyed in paise so that they can be ordered and compared exactly
+        let mut strikes: BTreeMap<i64, StrikePair<'a>> = BTreeMap::new();
+
+        for entry in &data.options_chain {
+            match entry {
+                ChainEntry::Underlying(u) => underlying = Some(u),
+                ChainEntry::Option(option) => {
+                    let pair = strikes
+                        .entry((option.strike_price * 100.0).round() as i64)
+                        .or_insert(StrikePair { strike: option.strike_price, call: None, put: None });
+                    match option.option_type.as_str() {
+                        "CE" => pair.call = Some(option),
+                        "PE" => pair.put = Some(option),
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        Self {
+            data,
+            underlying,
+            strikes: strikes.into_values().collect(),
+        }
+    }
+
+    /// The strike-wise call/put pairs, in ascending order of strike
+    pub fn strikes(&self) -> &[StrikePair<'a>] {
+        &self.strikes
+    }
+
+    /// The underlying security of the chain, if the API returned it
+    pub fn underlying(&self) -> Option<&'a UnderlyingData> {
+        self.underlying
+    }
+
+    /// # Description
+    /// Put-call ratio by open interest, using the chain totals returned by the API
+    pub fn pcr_oi(&self) -> Option<f64> {
+        ratio(self.data.put_oi, self.data.call_oi)
+    }
+
+    /// # Description
+    /// Put-call ratio by traded volume across the strikes in the chain
+    pub fn pcr_volume(&self) -> Option<f64> {
+        let call_volume = self.strikes.iter().filter_map(|s| s.call).map(|c| c.volume).sum();
+        let put_volume = self.strikes.iter().filter_map(|s| s.put).map(|p| p.volume).sum();
+        ratio(put_volume, call_volume)
+    }
+
+    /// # Description
+    /// The strike closest to the underlying's last traded price
+    pub fn atm_strike(&self) -> Option<f64> {
+        let ltp = self.underlying?.ltp;
+        self.strikes
+            .iter()
+            .map(|s| s.strike)
+            .min_by(|a, b| (a - ltp).abs().total_cmp(&(b - ltp).abs()))
+    }
+
+    /// # Description
+    /// The max pain strike: the expiry price at which option writers pay out the least to
+    /// option holders, given the current open interest.
+    pub fn max_pain(&self) -> Option<f64> {
+        self.strikes
+            .iter()
+            .map(|expiry| {
+                let payout: f64 = self
+                    .strikes
+                    .iter()
+                    .map(|s| {
+                        let call = (expiry.strike - s.strike).max(0.0) * s.call_oi() as f64;
+                        let put = (s.strike - expiry.strike).max(0.0) * s.put_oi() as f64;
+                        call + put
+                    })
+                    .sum();
+                (expiry.strike, payout)
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(strike, _)| strike)
+    }
+
+    /// # Description
+    /// OI change classification of every contract in the chain
+    pub fn buildups(&self) -> Vec<(&'a OptionData, OiBuildup)> {
+        self.strikes
+            .iter()
+            .flat_map(|s| s.call.into_iter().chain(s.put))
+            .map(|option| (option, OiBuildup::of(option)))
+            .collect()
+    }
+
+    /// # Description
+    /// Support levels: the strikes with the highest put open interest, highest first
+    ///
+    /// # Arguments
+    /// * `count` - The number of levels to return
+    pub fn support(&self, count: usize) -> Vec<(f64, i64)> {
+        top_by(&self.strikes, count, StrikePair::put_oi)
+    }
+
+    /// # Description
+    /// Resistance levels: the strikes with the highest call open interest, highest first
+    ///
+    /// # Arguments
+    /// * `count` - The number of levels to return
+    pub fn resistance(&self, count: usize) -> Vec<(f64, i64)> {
+        top_by(&self.strikes, count, StrikePair::call_oi)
+    }
+}
+
+fn ratio(numerator: i64, denominator: i64) -> Option<f64> {
+    if denominator == 0 {
+        None
+    } else {
+        Some(numerator as f64 / denominator as f64)
+    }
+}
+
+fn top_by<'a>(strikes: &[StrikePair<'a>], count: usize, oi: fn(&StrikePair<'a>) -> i64) -> Vec<(f64, i64)> {
+    let mut levels: Vec<(f64, i64)> = strikes
+        .iter()
+        .map(|s| (s.strike, oi(s)))
+        .filter(|(_, oi)| *oi > 0)
+        .collect();
+    levels.sort_by_key(|level| std::cmp::Reverse(level.1));
+    levels.truncate(count);
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::dataapi::IndiaVixData;
+
+    fn option(option_type: &str, strike_price: f64, oi: i64, volume: i64) -> ChainEntry {
+        ChainEntry::Option(OptionData {
+            ask: 0.0,
+            bid: 0.0,
+            fy_token: String::new(),
+            ltp: 0.0,
+            ltpch: 0.0,
+            ltpchp: 0.0,
+            oi,
+            oich: 0,
+            oichp: 0.0,
+            option_type: option_type.to_string(),
+            prev_oi: oi,
+            strike_price,
+            symbol: format!("NSE:NIFTY25SEP{}{}", strike_price, option_type),
+            volume,
+        })
+    }
+
+    fn underlying(ltp: f64) -> ChainEntry {
+        ChainEntry::Underlying(UnderlyingData {
+            ask: 0.0,
+            bid: 0.0,
+            description: String::new(),
+            ex_symbol: "NIFTY".to_string(),
+            exchange: "NSE".to_string(),
+            fp: 0.0,
+            fpch: 0.0,
+            fpchp: 0.0,
+            fy_token: String::new(),
+            ltp,
+            ltpch: 0.0,
+            ltpchp: 0.0,
+            option_type: String::new(),
+            strike_price: -1.0,
+            symbol: "NSE:NIFTY50-INDEX".to_string(),
+        })
+    }
+
+    fn chain(call_oi: i64, put_oi: i64, options_chain: Vec<ChainEntry>) -> ChainData {
+        ChainData {
+            call_oi,
+            put_oi,
+            expiry_data: Vec::new(),
+            indiavix_data: IndiaVixData {
+                ask: 0.0,
+                bid: 0.0,
+                description: String::new(),
+                ex_symbol: String::new(),
+                exchange: String::new(),
+                fy_token: String::new(),
+                ltp: 0.0,
+                ltpch: 0.0,
+                ltpchp: 0.0,
+                option_type: String::new(),
+                strike_price: -1.0,
+                symbol: String::new(),
+            },
+            options_chain,
+        }
+    }
+
+    #[test]
+    fn max_pain_is_the_strike_with_the_smallest_payout() {
+        // Payouts at expiry: 400 at 100, 200 at 110, 400 at 120
+        let data = chain(
+            60,
+            60,
+            vec![
+                option("CE", 100.0, 10, 0),
+                option("PE", 100.0, 30, 0),
+                option("CE", 110.0, 20, 0),
+                option("PE", 110.0, 20, 0),
+                option("CE", 120.0, 30, 0),
+                option("PE", 120.0, 10, 0),
+            ],
+        );
+        let analytics = ChainAnalytics::new(&data);
+        assert_eq!(analytics.max_pain(), Some(110.0));
+        assert_eq!(analytics.support(1), vec![(100.0, 30)]);
+        assert_eq!(analytics.resistance(1), vec![(120.0, 30)]);
+    }
+
+    #[test]
+    fn pcr_is_undefined_without_call_oi() {
+        let data = chain(0, 500, vec![option("PE", 100.0, 500, 40)]);
+        let analytics = ChainAnalytics::new(&data);
+        assert_eq!(analytics.pcr_oi(), None);
+        assert_eq!(analytics.pcr_volume(), None);
+        assert_eq!(analytics.strikes()[0].pcr(), None);
+
+        let data = chain(400, 500, vec![option("CE", 100.0, 400, 80), option("PE", 100.0, 500, 40)]);
+        let analytics = ChainAnalytics::new(&data);
+        assert_eq!(analytics.pcr_oi(), Some(1.25));
+        assert_eq!(analytics.pcr_volume(), Some(0.5));
+    }
+
+    #[test]
+    fn atm_takes_the_lower_strike_when_the_spot_is_halfway() {
+        let data = chain(
+            0,
+            0,
+            vec![underlying(22_050.0), option("CE", 22_100.0, 0, 0), option("CE", 22_000.0, 0, 0)],
+        );
+        let analytics = ChainAnalytics::new(&data);
+        assert_eq!(analytics.atm_strike(), Some(22_000.0));
+
+        let data = chain(0, 0, vec![option("CE", 22_000.0, 0, 0)]);
+        assert_eq!(ChainAnalytics::new(&data).atm_strike(), None);
+    }
+
+    #[test]
+    fn classifies_all_four_buildups() {
+        assert_eq!(OiBuildup::classify(5.0, 100), OiBuildup::LongBuildup);
+        assert_eq!(OiBuildup::classify(-5.0, 100), OiBuildup::ShortBuildup);
+        assert_eq!(OiBuildup::classify(5.0, -100), OiBuildup::ShortCovering);
+        assert_eq!(OiBuildup::classify(-5.0, -100), OiBuildup::LongUnwinding);
+        assert_eq!(OiBuildup::classify(0.0, 100), OiBuildup::Neutral);
+        assert_eq!(OiBuildup::classify(5.0, 0), OiBuildup::Neutral);
+        assert_eq!(OiBuildup::classify(f64::NAN, 100), OiBuildup::Neutral);
+    }
+}
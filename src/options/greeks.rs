@@ -0,0 +1,399 @@
+use std::f64::consts::PI;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::FyersError;
+use crate::models::dataapi::{ChainEntry, ExpiryData, OptionChainResponse};
+
+const SECONDS_PER_YEAR: f64 = 365.0 * 86_400.0;
+
+// Search bounds and tolerance for the implied volatility solver
+const MIN_VOLATILITY: f64 = 1e-4;
+const MAX_VOLATILITY: f64 = 5.0;
+const IV_TOLERANCE: f64 = 1e-6;
+const IV_MAX_ITERATIONS: usize = 100;
+
+/// Call or put
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OptionType {
+    Call,
+    Put,
+}
+
+impl OptionType {
+    /// # Description
+    /// Parse the `option_type` field of the option chain ("CE" or "PE")
+    pub fn from_fyers(option_type: &str) -> Option<Self> {
+        match option_type {
+            "CE" => Some(OptionType::Call),
+            "PE" => Some(OptionType::Put),
+            _ => None,
+        }
+    }
+}
+
+/// The pricing model used to value an option
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PricingModel {
+    /// Black-Scholes on the spot price, for stock options
+    BlackScholes,
+    /// Black-76 on the futures price, for index options
+    Black76,
+}
+
+impl PricingModel {
+    // Cost of carry of the underlying
+    fn carry(&self, rate: f64) -> f64 {
+        match self {
+            PricingModel::BlackScholes => rate,
+            PricingModel::Black76 => 0.0,
+        }
+    }
+}
+
+/// Option sensitivities
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Greeks {
+    pub delta: f64,
+    pub gamma: f64,
+    /// Change in option value per calendar day
+    pub theta: f64,
+    /// Change in option value per 1 point (0.01) change in volatility
+    pub vega: f64,
+    /// Change in option value per 1% change in the risk-free rate
+    pub rho: f64,
+}
+
+/// The inputs of a single option valuation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OptionParams {
+    pub option_type: OptionType,
+    pub model: PricingModel,
+    /// Spot price for Black-Scholes, futures price for Black-76
+    pub underlying: f64,
+    pub strike: f64,
+    /// Time to expiry, in years
+    pub time_to_expiry: f64,
+    /// Annualised risk-free rate (e.g. 0.065 for 6.5%)
+    pub rate: f64,
+}
+
+impl OptionParams {
+    fn d1_d2(&self, volatility: f64) -> (f64, f64) {
+        let b = self.model.carry(self.rate);
+        let vol_sqrt_t = volatility * self.time_to_expiry.sqrt();
+        let d1 = ((self.underlying / self.strike).ln() + (b + volatility * volatility / 2.0) * self.time_to_expiry) / vol_sqrt_t;
+        (d1, d1 - vol_sqrt_t)
+    }
+
+    fn intrinsic(&self) -> f64 {
+        let discount = (-self.rate * self.time_to_expiry).exp();
+        let carry = ((self.model.carry(self.rate) - self.rate) * self.time_to_expiry).exp();
+        let forward_value = self.underlying * carry - self.strike * discount;
+        match self.option_type {
+            OptionType::Call => forward_value.max(0.0),
+            OptionType::Put => (-forward_value).max(0.0),
+        }
+    }
+
+    /// # Description
+    /// Theoretical value of the option
+    ///
+    /// # Arguments
+    /// * `volatility` - Annualised volatility (e.g. 0.15 for 15%)
+    pub fn price(&self, volatility: f64) -> f64 {
+        if self.time_to_expiry <= 0.0 || volatility <= 0.0 {
+            return self.intrinsic();
+        }
+        let (d1, d2) = self.d1_d2(volatility);
+        let discount = (-self.rate * self.time_to_expiry).exp();
+        let carry = ((self.model.carry(self.rate) - self.rate) * self.time_to_expiry).exp();
+        match self.option_type {
+            OptionType::Call => self.underlying * carry * norm_cdf(d1) - self.strike * discount * norm_cdf(d2),
+            OptionType::Put => self.strike * discount * norm_cdf(-d2) - self.underlying * carry * norm_cdf(-d1),
+        }
+    }
+
+    /// # Description
+    /// Delta, gamma, theta, vega and rho of the option. The time to expiry and the volatility
+    /// must be positive.
+    ///
+    /// # Arguments
+    /// * `volatility` - Annualised volatility (e.g. 0.15 for 15%)
+    pub fn greeks(&self, volatility: f64) -> Greeks {
+        let t = self.time_to_expiry;
+        let (s, k, r) = (self.underlying, self.strike, self.rate);
+        let b = self.model.carry(r);
+        let (d1, d2) = self.d1_d2(volatility);
+        let discount = (-r * t).exp();
+        let carry = ((b - r) * t).exp();
+        let sqrt_t = t.sqrt();
+
+        let gamma = carry * norm_pdf(d1) / (s * volatility * sqrt_t);
+        let vega = s * carry * norm_pdf(d1) * sqrt_t;
+        let decay = -s * carry * norm_pdf(d1) * volatility / (2.0 * sqrt_t);
+
+        let (delta, theta, rho) = match self.option_type {
+            OptionType::Call => {
+                let theta = decay - (b - r) * s * carry * norm_cdf(d1) - r * k * discount * norm_cdf(d2);
+                let rho = match self.model {
+                    PricingModel::BlackScholes => t * k * discount * norm_cdf(d2),
+                    PricingModel::Black76 => -t * self.price(volatility),
+                };
+                (carry * norm_cdf(d1), theta, rho)
+            }
+            OptionType::Put => {
+                let theta = decay + (b - r) * s * carry * norm_cdf(-d1) + r * k * discount * norm_cdf(-d2);
+                let rho = match self.model {
+                    PricingModel::BlackScholes => -t * k * discount * norm_cdf(-d2),
+                    PricingModel::Black76 => -t * self.price(volatility),
+                };
+                (carry * (norm_cdf(d1) - 1.0), theta, rho)
+            }
+        };
+
+        Greeks {
+            delta,
+            gamma,
+            theta: theta / 365.0,
+            vega: vega / 100.0,
+            rho: rho / 100.0,
+        }
+    }
+
+    /// # Description
+    /// Solve for the volatility that prices the option at `price`. Returns `None` if the price is
+    /// outside the arbitrage bounds or the option has expired.
+    ///
+    /// # Arguments
+    /// * `price` - The market price of the option (e.g. its LTP)
+    pub fn implied_volatility(&self, price: f64) -> Option<f64> {
+        if self.time_to_expiry <= 0.0 || price <= self.intrinsic() || !price.is_finite() {
+            return None;
+        }
+        if price >= self.price(MAX_VOLATILITY) {
+            return None;
+        }
+
+        // Newton-Raphson, falling back to bisection whenever a step leaves the bracket
+        let (mut low, mut high) = (MIN_VOLATILITY, MAX_VOLATILITY);
+        let mut volatility = 0.3;
+        for _ in 0..IV_MAX_ITERATIONS {
+            let diff = self.price(volatility) - price;
+            if diff.abs() < IV_TOLERANCE {
+                return Some(volatility);
+            }
+            if diff > 0.0 {
+                high = volatility;
+            } else {
+                low = volatility;
+            }
+            let vega = self.greeks(volatility).vega * 100.0;
+            let next = volatility - diff / vega;
+            volatility = if vega > f64::EPSILON && next > low && next < high {
+                next
+            } else {
+                (low + high) / 2.0
+            };
+        }
+        Some(volatility)
+    }
+}
+
+/// Implied volatility and Greeks of a single contract in the option chain
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptionGreeks {
+    pub symbol: String,
+    pub option_type: OptionType,
+    pub strike: f64,
+    pub ltp: f64,
+    /// `None` if the implied volatility could not be solved (e.g. the LTP is below intrinsic)
+    pub iv: Option<f64>,
+    pub greeks: Option<Greeks>,
+}
+
+/// # Description
+/// Compute the implied volatility and Greeks of every contract in an option chain, as of now.
+///
+/// # Arguments
+/// * `response` - The response of [DataApi::get_option_chain](crate::dataapi::DataApi::get_option_chain)
+/// * `expiry` - The expiry the chain was fetched for, from its `expiry_data`
+/// * `rate` - Annualised risk-free rate (e.g. 0.065 for 6.5%)
+/// * `model` - [PricingModel::Black76] for index options, [PricingModel::BlackScholes] for stock
+///   options
+pub fn enrich_chain(
+    response: &OptionChainResponse,
+    expiry: &ExpiryData,
+    rate: f64,
+    model: PricingModel,
+) -> Result<Vec<OptionGreeks>, FyersError> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| FyersError::Unknown(e.to_string()))?
+        .as_secs() as i64;
+    enrich_chain_at(response, expiry, rate, model, now)
+}
+
+/// # Description
+/// Same as [enrich_chain], valued at the given time instead of now
+///
+/// # Arguments
+/// * `now` - The valuation time, in epoch seconds
+pub fn enrich_chain_at(
+    response: &OptionChainResponse,
+    expiry: &ExpiryData,
+    rate: f64,
+    model: PricingModel,
+    now: i64,
+) -> Result<Vec<OptionGreeks>, FyersError> {
+    let data = response
+        .data
+        .as_ref()
+        .ok_or_else(|| FyersError::Unknown("Option chain response has no data".to_string()))?;
+
+    let underlying = data
+        .options_chain
+        .iter()
+        .find_map(|entry| match entry {
+            ChainEntry::Underlying(u) => Some(u),
+            ChainEntry::Option(_) => None,
+        })
+        .ok_or_else(|| FyersError::Unknown("Option chain has no underlying".to_string()))?;
+
+    // Black-76 prices off the futures price, when the API reports one
+    let underlying_price = match model {
+        PricingModel::Black76 if underlying.fp > 0.0 => underlying.fp,
+        _ => underlying.ltp,
+    };
+    let time_to_expiry = (expiry.expiry - now).max(0) as f64 / SECONDS_PER_YEAR;
+
+    let enriched = data
+        .options_chain
+        .iter()
+        .filter_map(|entry| match entry {
+            ChainEntry::Option(option) => Some(option),
+            ChainEntry::Underlying(_) => None,
+        })
+        .filter_map(|option| {
+            let params = OptionParams {
+                option_type: OptionType::from_fyers(&option.option_type)?,
+                model,
+                underlying: underlying_price,
+                strike: option.strike_price,
+                time_to_expiry,
+                rate,
+            };
+            let iv = params.implied_volatility(option.ltp);
+            Some(OptionGreeks {
+                symbol: option.symbol.clone(),
+                option_type: params.option_type,
+                strike: option.strike_price,
+                ltp: option.ltp,
+                iv,
+                greeks: iv.map(|iv| params.greeks(iv)),
+            })
+        })
+        .collect();
+
+    Ok(enriched)
+}
+
+/// Standard normal probability density
+fn norm_pdf(x: f64) -> f64 {
+    (-x * x / 2.0).exp() / (2.0 * PI).sqrt()
+}
+
+/// Standard normal cumulative distribution
+fn norm_cdf(x: f64) -> f64 {
+    0.5 * erfc(-x / std::f64::consts::SQRT_2)
+}
+
+/// Complementary error function (Numerical Recipes, fractional error below 1.2e-7)
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let r = t * (-z * z - 1.265_512_23
+        + t * (1.000_023_68
+            + t * (0.374_091_96
+                + t * (0.096_784_18
+                    + t * (-0.186_288_06
+                        + t * (0.278_868_07
+                            + t * (-1.135_203_98
+                                + t * (1.488_515_87 + t * (-0.822_152_23 + t * 0.170_872_77)))))))))
+        .exp();
+    if x >= 0.0 {
+        r
+    } else {
+        2.0 - r
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(option_type: OptionType, model: PricingModel) -> OptionParams {
+        OptionParams {
+            option_type,
+            model,
+            underlying: 100.0,
+            strike: 100.0,
+            time_to_expiry: 1.0,
+            rate: 0.05,
+        }
+    }
+
+    #[test]
+    fn black_scholes_matches_reference_prices() {
+        let call = params(OptionType::Call, PricingModel::BlackScholes);
+        let put = params(OptionType::Put, PricingModel::BlackScholes);
+        assert!((call.price(0.2) - 10.4506).abs() < 1e-3);
+        assert!((put.price(0.2) - 5.5735).abs() < 1e-3);
+    }
+
+    #[test]
+    fn black_76_satisfies_put_call_parity() {
+        let call = OptionParams { underlying: 105.0, ..params(OptionType::Call, PricingModel::Black76) };
+        let put = OptionParams { underlying: 105.0, ..params(OptionType::Put, PricingModel::Black76) };
+        let parity = (-0.05f64).exp() * (105.0 - 100.0);
+        assert!((call.price(0.25) - put.price(0.25) - parity).abs() < 1e-9);
+    }
+
+    #[test]
+    fn greeks_match_finite_differences() {
+        let call = params(OptionType::Call, PricingModel::BlackScholes);
+        let greeks = call.greeks(0.2);
+        let bump = 0.01;
+        let up = OptionParams { underlying: 100.0 + bump, ..call };
+        let down = OptionParams { underlying: 100.0 - bump, ..call };
+        let delta = (up.price(0.2) - down.price(0.2)) / (2.0 * bump);
+        let gamma = (up.price(0.2) - 2.0 * call.price(0.2) + down.price(0.2)) / (bump * bump);
+        let vega = (call.price(0.21) - call.price(0.19)) / 2.0;
+
+        assert!((greeks.delta - 0.6368).abs() < 1e-3);
+        assert!((greeks.delta - delta).abs() < 1e-4);
+        assert!((greeks.gamma - gamma).abs() < 1e-4);
+        assert!((greeks.vega - vega).abs() < 1e-4);
+        assert!(greeks.theta < 0.0);
+    }
+
+    #[test]
+    fn implied_volatility_round_trips() {
+        for model in [PricingModel::BlackScholes, PricingModel::Black76] {
+            for option_type in [OptionType::Call, OptionType::Put] {
+                let option = OptionParams { strike: 110.0, ..params(option_type, model) };
+                let price = option.price(0.35);
+                let iv = option.implied_volatility(price).unwrap();
+                assert!((iv - 0.35).abs() < 1e-5, "{:?} {:?}: {}", model, option_type, iv);
+            }
+        }
+    }
+
+    #[test]
+    fn implied_volatility_rejects_prices_outside_bounds() {
+        let put = OptionParams { strike: 120.0, ..params(OptionType::Put, PricingModel::BlackScholes) };
+        assert_eq!(put.implied_volatility(1.0), None);
+        assert_eq!(put.implied_volatility(f64::NAN), None);
+        let expired = OptionParams { time_to_expiry: 0.0, ..put };
+        assert_eq!(expired.implied_volatility(25.0), None);
+    }
+}
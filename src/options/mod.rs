@@ -0,0 +1,5 @@
+pub mod analytics;
+pub mod greeks;
+
+pub use analytics::{ChainAnalytics, OiBuildup, StrikePair};
+pub use greeks::{enrich_chain, enrich_chain_at, Greeks, OptionGreeks, OptionParams, OptionType, PricingModel};
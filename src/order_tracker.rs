@@ -0,0 +1,418 @@
+use crate::error::FyersError;
+use crate::models::transaction::{Order, Trade};
+use crate::round_trips::parse_order_time;
+use crate::token_store::unix_now;
+use crate::transaction::Transaction;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// How many more times [OrderTracker::poll_until_terminal] reads the trade book, once every order
+/// is terminal, for fills the trade book does not show yet
+const MAX_FILL_SYNC_POLLS: usize = 10;
+
+/// The lifecycle state of an order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OrderState {
+    /// Tracked, but not yet seen in the order book
+    Submitted,
+    Transit,
+    Pending,
+    /// Pending with part of the quantity filled
+    PartiallyFilled,
+    Traded,
+    Cancelled,
+    Rejected,
+    Expired,
+    /// A status code this crate does not know about
+    Unknown(i64),
+}
+
+impl OrderState {
+    /// # Description
+    /// The state of an order book entry, from its `status` and `filled_qty`
+    pub fn of(order: &Order) -> Self {
+        match order.status {
+            1 => OrderState::Cancelled,
+            2 => OrderState::Traded,
+            4 => OrderState::Transit,
+            5 => OrderState::Rejected,
+            6 if order.filled_qty > 0 => OrderState::PartiallyFilled,
+            6 => OrderState::Pending,
+            7 => OrderState::Expired,
+            code => OrderState::Unknown(code),
+        }
+    }
+
+    // How far along its lifecycle an order in this state is, to recognize stale updates
+    fn progress(&self) -> u8 {
+        match self {
+            OrderState::Submitted => 0,
+            OrderState::Transit => 1,
+            OrderState::Pending | OrderState::Unknown(_) => 2,
+            OrderState::PartiallyFilled => 3,
+            OrderState::Traded | OrderState::Cancelled | OrderState::Rejected | OrderState::Expired => 4,
+        }
+    }
+
+    /// Whether the order can no longer change
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            OrderState::Traded | OrderState::Cancelled | OrderState::Rejected | OrderState::Expired
+        )
+    }
+}
+
+/// A recorded change of an order's state
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateChange {
+    /// When the change was observed, in epoch seconds
+    pub at: i64,
+    pub from: Option<OrderState>,
+    pub to: OrderState,
+    /// Filled quantity when the change was observed
+    pub filled_qty: i64,
+    /// The order book message at the time, e.g. the reason for a rejection
+    pub message: String,
+}
+
+/// An order followed by an [OrderTracker]
+#[derive(Debug, Clone)]
+pub struct TrackedOrder {
+    pub id: String,
+    pub state: OrderState,
+    /// Every state change, oldest first
+    pub history: Vec<StateChange>,
+    /// The fills of the order from the trade book, oldest first
+    pub fills: Vec<Trade>,
+    /// The latest order book entry, `None` until the order is seen
+    pub last: Option<Order>,
+}
+
+impl TrackedOrder {
+    fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            state: OrderState::Submitted,
+            history: vec![StateChange {
+                at: unix_now(),
+                from: None,
+                to: OrderState::Submitted,
+                filled_qty: 0,
+                message: String::new(),
+            }],
+            fills: Vec::new(),
+            last: None,
+        }
+    }
+
+    pub fn filled_qty(&self) -> i64 {
+        self.last.as_ref().map_or(0, |order| order.filled_qty)
+    }
+
+    /// Average fill price, from the fills when there are any and the order book otherwise
+    pub fn average_price(&self) -> f64 {
+        let qty: i64 = self.fills.iter().map(|trade| trade.traded_qty).sum();
+        if qty > 0 {
+            self.fills.iter().map(|trade| trade.trade_price * trade.traded_qty as f64).sum::<f64>() / qty as f64
+        } else {
+            self.last.as_ref().map_or(0.0, |order| order.traded_price)
+        }
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        self.state.is_terminal()
+    }
+
+    /// Whether the fills from the trade book add up to the filled quantity of the order book
+    pub fn fills_complete(&self) -> bool {
+        self.fills.iter().map(|trade| trade.traded_qty).sum::<i64>() >= self.filled_qty()
+    }
+}
+
+/// # Description
+/// Follows orders from submission to a terminal state, recording every state change and the
+/// fills of each order. Feed it order book entries, either from the order websocket with
+/// [OrderTracker::on_order_update] or by polling with [OrderTracker::poll].
+#[derive(Debug, Clone, Default)]
+pub struct OrderTracker {
+    orders: HashMap<String, TrackedOrder>,
+}
+
+impl OrderTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Description
+    /// Start tracking an order, e.g. with the id returned by
+    /// [Order::single_order](crate::orders::Order::single_order). Tracking an order again has no
+    /// effect.
+    ///
+    /// # Arguments
+    /// * `id` - The order id
+    pub fn track(&mut self, id: &str) {
+        self.orders.entry(id.to_string()).or_insert_with(|| TrackedOrder::new(id));
+    }
+
+    /// # Description
+    /// Stop tracking an order and return what was recorded for it
+    pub fn untrack(&mut self, id: &str) -> Option<TrackedOrder> {
+        self.orders.remove(id)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&TrackedOrder> {
+        self.orders.get(id)
+    }
+
+    pub fn orders(&self) -> impl Iterator<Item = &TrackedOrder> {
+        self.orders.values()
+    }
+
+    /// Whether every tracked order has reached a terminal state
+    pub fn all_terminal(&self) -> bool {
+        self.orders.values().all(TrackedOrder::is_terminal)
+    }
+
+    /// # Description
+    /// Apply an order book entry. Entries for orders that are not tracked are ignored, and so are
+    /// stale entries that arrive out of order: any entry once the order is terminal, and entries
+    /// that are earlier in the lifecycle or show less filled than what was already seen. Returns
+    /// the state change, if the state changed.
+    ///
+    /// # Arguments
+    /// * `order` - The order book entry, from `/orders` or the order websocket
+    pub fn on_order_update(&mut self, order: &Order) -> Option<StateChange> {
+        let tracked = self.orders.get_mut(&order.id)?;
+        let state = OrderState::of(order);
+        let filled_before = tracked.filled_qty();
+        if tracked.is_terminal() || state.progress() < tracked.state.progress() || order.filled_qty < filled_before {
+            return None;
+        }
+        tracked.last = Some(order.clone());
+
+        // A partial fill that grows is recorded as a change too, so every fill shows in the history
+        if state == tracked.state && !(state == OrderState::PartiallyFilled && order.filled_qty > filled_before) {
+            return None;
+        }
+        let change = StateChange {
+            at: unix_now(),
+            from: Some(tracked.state),
+            to: state,
+            filled_qty: order.filled_qty,
+            message: order.message.clone(),
+        };
+        tracked.state = state;
+        tracked.history.push(change.clone());
+        Some(change)
+    }
+
+    /// # Description
+    /// Apply trade book entries, adding each fill to the order it belongs to (by `order_number`).
+    /// Fills that were already recorded are skipped.
+    ///
+    /// # Arguments
+    /// * `trades` - The trade book entries
+    pub fn on_trades(&mut self, trades: &[Trade]) {
+        for trade in trades {
+            let Some(tracked) = self.orders.get_mut(&trade.order_number) else {
+                continue;
+            };
+            if tracked.fills.iter().any(|fill| fill.trade_number == trade.trade_number) {
+                continue;
+            }
+            tracked.fills.push(trade.clone());
+            tracked
+                .fills
+                .sort_by_key(|fill| (parse_order_time(&fill.order_date_time).unwrap_or(i64::MIN), fill.row));
+        }
+    }
+
+    /// # Description
+    /// Read the order book once and apply it. The trade book is read as well when a tracked
+    /// order's filled quantity changed, or while the trade book is behind the order book. Returns
+    /// the state changes, by order id.
+    ///
+    /// # Arguments
+    /// * `transaction` - The client to read the order and trade books with
+    pub async fn poll(&mut self, transaction: &Transaction) -> Result<Vec<(String, StateChange)>, FyersError> {
+        let mut changes = Vec::new();
+        let mut fills_changed = false;
+        if !self.all_terminal() {
+            let orders = transaction.get_orders(None, None).await?;
+            for order in &orders.order_book {
+                let Some(filled_before) = self.orders.get(&order.id).map(TrackedOrder::filled_qty) else {
+                    continue;
+                };
+                fills_changed |= order.filled_qty != filled_before;
+                if let Some(change) = self.on_order_update(order) {
+                    changes.push((order.id.clone(), change));
+                }
+            }
+        }
+
+        if fills_changed || !self.fills_complete() {
+            let trades = transaction.get_trades(None).await?;
+            self.on_trades(&trades.trade_book);
+        }
+        Ok(changes)
+    }
+
+    /// Whether the fills of every tracked order add up to its filled quantity
+    pub fn fills_complete(&self) -> bool {
+        self.orders.values().all(TrackedOrder::fills_complete)
+    }
+
+    /// # Description
+    /// Poll until every tracked order has reached a terminal state, calling `on_change` for
+    /// each state change. Once they have, the trade book is read a few more times if it is still
+    /// missing fills.
+    ///
+    /// # Arguments
+    /// * `transaction` - The client to read the order and trade books with
+    /// * `interval` - Time between polls
+    /// * `on_change` - Called with the order and its state change
+    pub async fn poll_until_terminal<F>(
+        &mut self,
+        transaction: &Transaction,
+        interval: Duration,
+        mut on_change: F,
+    ) -> Result<(), FyersError>
+    where
+        F: FnMut(&TrackedOrder, &StateChange),
+    {
+        let mut fill_sync_polls = 0;
+        loop {
+            for (id, change) in self.poll(transaction).await? {
+                if let Some(tracked) = self.orders.get(&id) {
+                    on_change(tracked, &change);
+                }
+            }
+            if self.all_terminal() {
+                if self.fills_complete() || fill_sync_polls == MAX_FILL_SYNC_POLLS {
+                    return Ok(());
+                }
+                fill_sync_polls += 1;
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::transaction::test_order;
+
+    fn fill(trade_number: &str, order_date_time: &str, qty: i64) -> Trade {
+        Trade {
+            symbol: "NSE:SBIN-EQ".to_string(),
+            row: 0,
+            order_date_time: order_date_time.to_string(),
+            order_number: "1".to_string(),
+            trade_number: trade_number.to_string(),
+            trade_price: 800.0,
+            trade_value: 800.0 * qty as f64,
+            traded_qty: qty,
+            side: 1,
+            product_type: "CNC".to_string(),
+            exchange_order_no: String::new(),
+            segment: 10,
+            exchange: 10,
+            fy_token: String::new(),
+            order_tag: String::new(),
+        }
+    }
+
+    #[test]
+    fn fills_are_ordered_by_time_across_months() {
+        let mut tracker = OrderTracker::new();
+        tracker.track("1");
+        tracker.on_trades(&[
+            fill("b", "02-Oct-2025 09:20:00", 5),
+            fill("a", "30-Sep-2025 15:10:00", 5),
+            fill("c", "02-Oct-2025 10:05:00", 5),
+        ]);
+
+        let order: Vec<&str> = tracker.get("1").unwrap().fills.iter().map(|f| f.trade_number.as_str()).collect();
+        assert_eq!(order, ["a", "b", "c"]);
+    }
+
+    fn update(status: i64, filled_qty: i64) -> Order {
+        let mut order = test_order("1", "NSE:SBIN-EQ", 1, 100, status);
+        order.filled_qty = filled_qty;
+        order.remaining_quantity = 100 - filled_qty;
+        order
+    }
+
+    fn transition(change: Option<StateChange>) -> Option<(Option<OrderState>, OrderState, i64)> {
+        change.map(|change| (change.from, change.to, change.filled_qty))
+    }
+
+    #[test]
+    fn records_the_lifecycle_of_an_order() {
+        let mut tracker = OrderTracker::new();
+        tracker.track("1");
+
+        let transit = tracker.on_order_update(&update(4, 0));
+        assert_eq!(transition(transit), Some((Some(OrderState::Submitted), OrderState::Transit, 0)));
+        let pending = tracker.on_order_update(&update(6, 0));
+        assert_eq!(transition(pending), Some((Some(OrderState::Transit), OrderState::Pending, 0)));
+        let traded = tracker.on_order_update(&update(2, 100));
+        assert_eq!(transition(traded), Some((Some(OrderState::Pending), OrderState::Traded, 100)));
+
+        let order = tracker.get("1").unwrap();
+        assert!(order.is_terminal());
+        assert_eq!(order.history.len(), 4);
+        assert_eq!(order.filled_qty(), 100);
+    }
+
+    #[test]
+    fn records_every_partial_fill_until_traded() {
+        let mut tracker = OrderTracker::new();
+        tracker.track("1");
+        tracker.on_order_update(&update(6, 0));
+
+        let first = tracker.on_order_update(&update(6, 25));
+        assert_eq!(transition(first), Some((Some(OrderState::Pending), OrderState::PartiallyFilled, 25)));
+        let second = tracker.on_order_update(&update(6, 60));
+        assert_eq!(transition(second), Some((Some(OrderState::PartiallyFilled), OrderState::PartiallyFilled, 60)));
+        let traded = tracker.on_order_update(&update(2, 100));
+        assert_eq!(transition(traded), Some((Some(OrderState::PartiallyFilled), OrderState::Traded, 100)));
+    }
+
+    #[test]
+    fn ignores_duplicate_and_stale_updates() {
+        let mut tracker = OrderTracker::new();
+        tracker.track("1");
+        assert!(tracker.on_order_update(&update(6, 60)).is_some());
+
+        assert_eq!(tracker.on_order_update(&update(6, 60)), None);
+        assert_eq!(tracker.on_order_update(&update(6, 25)), None);
+        assert_eq!(tracker.on_order_update(&update(4, 0)), None);
+        assert_eq!(tracker.get("1").unwrap().filled_qty(), 60);
+
+        assert!(tracker.on_order_update(&update(2, 100)).is_some());
+        assert_eq!(tracker.on_order_update(&update(6, 60)), None);
+        assert_eq!(tracker.on_order_update(&update(1, 100)), None);
+        assert_eq!(tracker.get("1").unwrap().state, OrderState::Traded);
+
+        let mut untracked = update(6, 0);
+        untracked.id = "2".to_string();
+        assert_eq!(tracker.on_order_update(&untracked), None);
+    }
+
+    #[test]
+    fn records_a_rejection_after_pending() {
+        let mut tracker = OrderTracker::new();
+        tracker.track("1");
+        tracker.on_order_update(&update(6, 0));
+
+        let mut rejected = update(5, 0);
+        rejected.message = "RMS: insufficient margin".to_string();
+        let change = tracker.on_order_update(&rejected).unwrap();
+        assert_eq!((change.from, change.to), (Some(OrderState::Pending), OrderState::Rejected));
+        assert_eq!(change.message, "RMS: insufficient margin");
+        assert!(tracker.all_terminal());
+    }
+}
@@ -1,6 +1,17 @@
+use crate::credentials::Credentials;
 use crate::error::FyersError;
+use crate::token_store::TokenStore;
+use crate::transaction::Transaction;
 use crate::models::{ SingleOrderResponse, MultipleOrdersResponse, SingleOrderRequest };
+use crate::models::orders::{CancelOrderRequest, ModifyOrderRequest};
+use crate::models::orders::{BasketMargin, MarginLeg, MarginRequest, MarginResponse};
+use crate::models::orders::{LegStatus, MultiLegLegs, MultiLegOrderRequest, MultiLegOrderResponse, MultiLegOrderResult, OrderLeg};
+use crate::models::orders::{GttCancelRequest, GttModifyRequest, GttOrderInfo, GttOrderRequest, GttOrderResponse, GttOrdersResponse};
+use futures_util::future::{join, join_all};
+use crate::models::transaction::Order as BookedOrder;
+use crate::rate_limiter::RateLimiter;
 use reqwest::Client;
+use std::sync::Arc;
 
 const FYERS_API_BASE_URL: &str = "https://api-t1.fyers.in/api/v3";
 
@@ -8,8 +19,8 @@ const FYERS_API_BASE_URL: &str = "https://api-t1.fyers.in/api/v3";
 #[derive(Debug, Clone)]
 pub struct Order {
     http_client: Client,
-    app_id: String,
-    access_token: String,
+    credentials: Credentials,
+    rate_limiter: Arc<RateLimiter>,
 }
 
 impl Order {
@@ -22,11 +33,46 @@ impl Order {
     pub fn new(app_id: String, access_token: String) -> Self {
         Self {
             http_client: Client::new(),
-            app_id,
-            access_token
+            credentials: Credentials::new(app_id, access_token),
+            rate_limiter: Arc::new(RateLimiter::default()),
         }
     }
 
+    /// # Description
+    /// Create a new instance of the Order class that reads its access token from a token store.
+    /// Calls fail with an auth error once the stored token has expired.
+    ///
+    /// # Arguments
+    /// * `app_id` - The app id of the user.
+    /// * `token_store` - The token store holding the access token of the user.
+    pub fn from_token_store(app_id: String, token_store: Arc<dyn TokenStore>) -> Result<Self, FyersError> {
+        Ok(Self {
+            http_client: Client::new(),
+            credentials: Credentials::from_token_store(app_id, token_store)?,
+            rate_limiter: Arc::new(RateLimiter::default()),
+        })
+    }
+
+    /// Create a client that shares its credentials, and so its logout, with other clients
+    pub(crate) fn with_credentials(credentials: Credentials) -> Self {
+        Self {
+            http_client: Client::new(),
+            credentials,
+            rate_limiter: Arc::new(RateLimiter::default()),
+        }
+    }
+
+    /// # Description
+    /// Use a custom rate limiter for the calls that fan out into several requests. Pass the same
+    /// limiter to several clients to limit them together.
+    ///
+    /// # Arguments
+    /// * `rate_limiter` - The rate limiter to use
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
     /// # Description
     /// Place a single order to any exchange. [Read more](https://myapi.fyers.in/docsv3#tag/Order-Placement)
     ///
@@ -34,7 +80,7 @@ impl Order {
     /// * `order` - The order to place, as a SingleOrderRequest
     pub async fn single_order(&self, order: &SingleOrderRequest) -> Result<SingleOrderResponse, FyersError> {
         let url = format!("{}/orders/sync", FYERS_API_BASE_URL);
-        let auth_header_value = format!("{}:{}", self.app_id, self.access_token);
+        let auth_header_value = self.credentials.auth_header()?;
         let response = self
             .http_client
             .post(&url)
@@ -74,4 +120,414 @@ impl Order {
     pub async fn multiple_orders(&self, orders: &Vec<SingleOrderRequest>) -> Result<MultipleOrdersResponse, FyersError> {
         unimplemented!()
     }
+
+    /// # Description
+    /// Modify a pending order. Only the fields set on the request are changed. [Read more](https://myapi.fyers.in/docsv3#tag/Order-Placement)
+    ///
+    /// # Arguments
+    /// * `request` - The modification, as a ModifyOrderRequest
+    pub async fn modify_order(&self, request: &ModifyOrderRequest) -> Result<SingleOrderResponse, FyersError> {
+        self.send_order_request(reqwest::Method::PATCH, request).await
+    }
+
+    /// # Description
+    /// Cancel a pending order. [Read more](https://myapi.fyers.in/docsv3#tag/Order-Placement)
+    ///
+    /// # Arguments
+    /// * `id` - The id of the order to cancel
+    pub async fn cancel_order(&self, id: &str) -> Result<SingleOrderResponse, FyersError> {
+        let request = CancelOrderRequest { id: id.to_string() };
+        self.send_order_request(reqwest::Method::DELETE, &request).await
+    }
+
+    /// # Description
+    /// Place a multi-leg (2L or 3L) order. The legs are sent to the exchange as one order, so
+    /// either all of them are placed or none is. After placement the order book is read to
+    /// report the status of every leg. [Read more](https://myapi.fyers.in/docsv3#tag/Order-Placement)
+    ///
+    /// # Arguments
+    /// * `order` - The order to place, as a MultiLegOrderRequest
+    pub async fn multi_leg_order(&self, order: &MultiLegOrderRequest) -> Result<MultiLegOrderResult, FyersError> {
+        order.validate().map_err(FyersError::InvalidRequest)?;
+
+        let url = format!("{}/multileg/orders/sync", FYERS_API_BASE_URL);
+        let auth_header_value = self.credentials.auth_header()?;
+        let response = self
+            .http_client
+            .post(&url)
+            .header("Authorization", auth_header_value)
+            .json(order)
+            .send()
+            .await?;
+
+        // First we check if API returned a non-success status code
+        if !response.status().is_success() {
+            return Err(FyersError::Network(response.error_for_status().unwrap_err()));
+        }
+
+        let response_text = response.text().await?;
+        let order_response: MultiLegOrderResponse = serde_json::from_str(&response_text)?;
+        if order_response.s != "ok" {
+            return Err(FyersError::ApiError {
+                s: order_response.s,
+                code: order_response.code,
+                message: order_response.message,
+            });
+        }
+
+        // The order was placed, so a failure to read the order book must not be reported as a
+        // failed placement
+        let legs = match self.get_multi_leg_status(&order_response.id, &order.legs).await {
+            Ok(legs) => legs,
+            Err(e) => order
+                .legs
+                .iter()
+                .map(|(name, leg)| LegStatus {
+                    leg: name.to_string(),
+                    symbol: leg.symbol.clone(),
+                    order_id: None,
+                    status: None,
+                    filled_qty: 0,
+                    traded_price: 0.0,
+                    message: format!("Could not read the order book: {}", e),
+                })
+                .collect(),
+        };
+
+        Ok(MultiLegOrderResult { response: order_response, legs })
+    }
+
+    /// # Description
+    /// Read the status of every leg of a multi-leg order from the order book
+    ///
+    /// # Arguments
+    /// * `id` - The id returned when the order was placed
+    /// * `legs` - The legs of the order
+    pub async fn get_multi_leg_status(&self, id: &str, legs: &MultiLegLegs) -> Result<Vec<LegStatus>, FyersError> {
+        // An empty id would match every order without a parent
+        if id.is_empty() {
+            return Err(FyersError::InvalidRequest("the multi-leg order id is empty".to_string()));
+        }
+        let transaction = Transaction::with_credentials(self.credentials.clone());
+        let orders_response = transaction.get_orders(None, None).await?;
+        Ok(match_legs(id, legs, &orders_response.order_book))
+    }
+
+    /// # Description
+    /// Calculate the margin required by a list of orders, taking into account the margin
+    /// already blocked by open positions. [Read more](https://myapi.fyers.in/docsv3#tag/Order-Placement)
+    ///
+    /// # Arguments
+    /// * `orders` - The orders to calculate the margin of
+    pub async fn margin_calculator(&self, orders: &[SingleOrderRequest]) -> Result<MarginResponse, FyersError> {
+        if orders.is_empty() {
+            return Err(FyersError::InvalidRequest("the margin of an empty basket was requested".to_string()));
+        }
+        let request = MarginRequest {
+            data: orders.iter().map(MarginLeg::from).collect(),
+        };
+
+        let url = format!("{}/multiorder/margin", FYERS_API_BASE_URL);
+        let auth_header_value = self.credentials.auth_header()?;
+        let response = self
+            .http_client
+            .post(&url)
+            .header("Authorization", auth_header_value)
+            .json(&request)
+            .send()
+            .await?;
+
+        // First we check if API returned a non-success status code
+        if !response.status().is_success() {
+            return Err(FyersError::Network(response.error_for_status().unwrap_err()));
+        }
+
+        let response_text = response.text().await?;
+        let margin_response: MarginResponse = serde_json::from_str(&response_text)?;
+
+        if margin_response.s == "ok" {
+            Ok(margin_response)
+        } else {
+            Err(FyersError::ApiError {
+                s: margin_response.s,
+                code: margin_response.code,
+                message: margin_response.message,
+            })
+        }
+    }
+
+    /// # Description
+    /// Calculate the margin of a basket of orders, and the benefit of its legs hedging each
+    /// other. The basket is priced as a whole and every leg on its own, so this costs one request
+    /// per leg plus one for the basket. The requests are sent concurrently, spaced out by the
+    /// client's rate limiter.
+    ///
+    /// # Arguments
+    /// * `orders` - The legs of the basket
+    pub async fn basket_margin<'a>(&'a self, orders: &'a [SingleOrderRequest]) -> Result<BasketMargin, FyersError> {
+        let limited_margin = |orders: &'a [SingleOrderRequest]| async move {
+            self.rate_limiter.acquire().await;
+            self.margin_calculator(orders).await
+        };
+        let legs = orders.iter().map(|order| limited_margin(std::slice::from_ref(order)));
+        let (basket, legs) = join(limited_margin(orders), join_all(legs)).await;
+        let basket = basket?;
+
+        let leg_margins = legs
+            .into_iter()
+            .map(|leg| leg.map(|leg| leg.data.margin_new_order))
+            .collect::<Result<Vec<f64>, FyersError>>()?;
+        let standalone_margin: f64 = leg_margins.iter().sum();
+        let total_margin = basket.data.margin_new_order;
+
+        Ok(BasketMargin {
+            total_margin,
+            available_margin: basket.data.margin_avail,
+            leg_margins,
+            standalone_margin,
+            margin_benefit: standalone_margin - total_margin,
+        })
+    }
+
+    /// # Description
+    /// Place a GTT (Good Till Triggered) order, single or OCO. [Read more](https://myapi.fyers.in/docsv3#tag/Order-Placement)
+    ///
+    /// # Arguments
+    /// * `order` - The GTT order to place, as a GttOrderRequest
+    pub async fn place_gtt_order(&self, order: &GttOrderRequest) -> Result<GttOrderResponse, FyersError> {
+        self.send_gtt_request(reqwest::Method::POST, order).await
+    }
+
+    /// # Description
+    /// Modify the legs of a pending GTT order. [Read more](https://myapi.fyers.in/docsv3#tag/Order-Placement)
+    ///
+    /// # Arguments
+    /// * `id` - The id of the GTT order
+    /// * `order_info` - The new legs of the order
+    pub async fn modify_gtt_order(&self, id: &str, order_info: GttOrderInfo) -> Result<GttOrderResponse, FyersError> {
+        let request = GttModifyRequest { id: id.to_string(), order_info };
+        self.send_gtt_request(reqwest::Method::PATCH, &request).await
+    }
+
+    /// # Description
+    /// Cancel a pending GTT order. [Read more](https://myapi.fyers.in/docsv3#tag/Order-Placement)
+    ///
+    /// # Arguments
+    /// * `id` - The id of the GTT order
+    pub async fn cancel_gtt_order(&self, id: &str) -> Result<GttOrderResponse, FyersError> {
+        let request = GttCancelRequest { id: id.to_string() };
+        self.send_gtt_request(reqwest::Method::DELETE, &request).await
+    }
+
+    /// # Description
+    /// Fetch all the GTT orders of the user. [Read more](https://myapi.fyers.in/docsv3#tag/Transaction-Info)
+    pub async fn get_gtt_orders(&self) -> Result<GttOrdersResponse, FyersError> {
+        let url = format!("{}/gtt/orders", FYERS_API_BASE_URL);
+        let auth_header_value = self.credentials.auth_header()?;
+        let response = self
+            .http_client
+            .get(&url)
+            .header("Authorization", auth_header_value)
+            .send()
+            .await?;
+
+        // First we check if API returned a non-success status code
+        if !response.status().is_success() {
+            return Err(FyersError::Network(response.error_for_status().unwrap_err()));
+        }
+
+        let response_text = response.text().await?;
+        let orders_response: GttOrdersResponse = serde_json::from_str(&response_text)?;
+
+        if orders_response.s == "ok" {
+            Ok(orders_response)
+        } else {
+            Err(FyersError::ApiError {
+                s: orders_response.s,
+                code: orders_response.code,
+                message: orders_response.message,
+            })
+        }
+    }
+
+    // Private helper that sends a place, modify or cancel request to the GTT endpoint
+    async fn send_gtt_request<T: serde::Serialize>(&self, method: reqwest::Method, body: &T) -> Result<GttOrderResponse, FyersError> {
+        let url = format!("{}/gtt/orders/sync", FYERS_API_BASE_URL);
+        let auth_header_value = self.credentials.auth_header()?;
+        let response = self
+            .http_client
+            .request(method, &url)
+            .header("Authorization", auth_header_value)
+            .json(body)
+            .send()
+            .await?;
+
+        // First we check if API returned a non-success status code
+        if !response.status().is_success() {
+            return Err(FyersError::Network(response.error_for_status().unwrap_err()));
+        }
+
+        let response_text = response.text().await?;
+        let order_response: GttOrderResponse = serde_json::from_str(&response_text)?;
+
+        if order_response.s == "ok" {
+            Ok(order_response)
+        } else {
+            Err(FyersError::ApiError {
+                s: order_response.s,
+                code: order_response.code,
+                message: order_response.message,
+            })
+        }
+    }
+
+    // Private helper that sends a modify or cancel request to the orders endpoint
+    async fn send_order_request<T: serde::Serialize>(&self, method: reqwest::Method, body: &T) -> Result<SingleOrderResponse, FyersError> {
+        let url = format!("{}/orders/sync", FYERS_API_BASE_URL);
+        let auth_header_value = self.credentials.auth_header()?;
+        let response = self
+            .http_client
+            .request(method, &url)
+            .header("Authorization", auth_header_value)
+            .json(body)
+            .send()
+            .await?;
+
+        // First we check if API returned a non-success status code
+        if !response.status().is_success() {
+            return Err(FyersError::Network(response.error_for_status().unwrap_err()));
+        }
+
+        let response_text = response.text().await?;
+        let order_response: SingleOrderResponse = serde_json::from_str(&response_text)?;
+
+        if order_response.s == "ok" {
+            Ok(order_response)
+        } else {
+            Err(FyersError::ApiError {
+                s: order_response.s,
+                code: order_response.code,
+                message: order_response.message,
+            })
+        }
+    }
+}
+
+/// # Description
+/// Match the legs of a multi-leg order with the orders booked for it, which belong to its id.
+/// Every booked order is matched with at most one leg: on symbol, side, quantity and limit price
+/// first, then on symbol and side alone for legs that are left over (e.g. after a price change).
+fn match_legs(id: &str, legs: &MultiLegLegs, order_book: &[BookedOrder]) -> Vec<LegStatus> {
+    let booked: Vec<&BookedOrder> = order_book.iter().filter(|o| o.id == id || o.parent_id == id).collect();
+    let legs: Vec<(&str, &OrderLeg)> = legs.iter().collect();
+    let mut taken = vec![false; booked.len()];
+    let mut matched: Vec<Option<&BookedOrder>> = vec![None; legs.len()];
+
+    for exact in [true, false] {
+        for (index, (_, leg)) in legs.iter().enumerate() {
+            if matched[index].is_some() {
+                continue;
+            }
+            let found = booked.iter().enumerate().position(|(i, o)| {
+                !taken[i]
+                    && o.symbol == leg.symbol
+                    && o.side == leg.side
+                    && (!exact || (o.qty == leg.qty && (o.limit_price - leg.limit_price).abs() < 1e-9))
+            });
+            if let Some(i) = found {
+                taken[i] = true;
+                matched[index] = Some(booked[i]);
+            }
+        }
+    }
+
+    legs.iter()
+        .zip(matched)
+        .map(|((name, leg), booked)| LegStatus {
+            leg: name.to_string(),
+            symbol: leg.symbol.clone(),
+            order_id: booked.map(|o| o.id.clone()),
+            status: booked.map(|o| o.status),
+            filled_qty: booked.map_or(0, |o| o.filled_qty),
+            traded_price: booked.map_or(0.0, |o| o.traded_price),
+            message: booked.map_or_else(|| "Leg not found in the order book".to_string(), |o| o.message.clone()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::transaction::test_order;
+
+    const CALL: &str = "NSE:NIFTY25SEP25000CE";
+    const PUT: &str = "NSE:NIFTY25SEP25000PE";
+
+    fn booked(id: &str, parent_id: &str, symbol: &str, side: i64, qty: i64, limit_price: f64) -> BookedOrder {
+        let mut order = test_order(id, symbol, side, qty, 6);
+        order.parent_id = parent_id.to_string();
+        order.limit_price = limit_price;
+        order
+    }
+
+    #[test]
+    fn legs_with_the_same_symbol_and_side_get_their_own_orders() {
+        let legs = MultiLegLegs {
+            leg1: OrderLeg::limit(CALL, 75, -1, 120.0),
+            leg2: OrderLeg::limit(CALL, 75, -1, 125.0),
+            leg3: Some(OrderLeg::limit(PUT, 75, 1, 80.0)),
+        };
+        let order_book = [
+            booked("ML-1-2", "ML-1", CALL, -1, 75, 125.0),
+            booked("ML-1", "", CALL, -1, 75, 120.0),
+            booked("ML-1-3", "ML-1", PUT, 1, 75, 80.0),
+            booked("OTHER", "", CALL, -1, 75, 120.0),
+        ];
+
+        let ids: Vec<Option<String>> = match_legs("ML-1", &legs, &order_book).into_iter().map(|leg| leg.order_id).collect();
+        assert_eq!(ids, [Some("ML-1".to_string()), Some("ML-1-2".to_string()), Some("ML-1-3".to_string())]);
+    }
+
+    #[test]
+    fn unmatched_legs_are_reported_missing() {
+        let legs = MultiLegLegs {
+            leg1: OrderLeg::limit(CALL, 75, -1, 120.0),
+            leg2: OrderLeg::limit(CALL, 75, -1, 125.0),
+            leg3: None,
+        };
+        // Only one order was booked, at a modified price
+        let order_book = [booked("ML-1", "", CALL, -1, 75, 118.0)];
+
+        let statuses = match_legs("ML-1", &legs, &order_book);
+        assert_eq!(statuses[0].order_id.as_deref(), Some("ML-1"));
+        assert_eq!(statuses[1].order_id, None);
+        assert_eq!(statuses[1].message, "Leg not found in the order book");
+    }
+
+    #[test]
+    fn validates_the_number_of_legs() {
+        let leg = || OrderLeg::limit(CALL, 75, 1, 100.0);
+        assert!(MultiLegOrderRequest::two_leg("INTRADAY", leg(), leg()).validate().is_ok());
+        assert!(MultiLegOrderRequest::three_leg("MARGIN", leg(), leg(), leg()).validate().is_ok());
+
+        let mut two = MultiLegOrderRequest::two_leg("INTRADAY", leg(), leg());
+        two.legs.leg3 = Some(leg());
+        assert!(two.validate().is_err());
+        let mut three = MultiLegOrderRequest::three_leg("INTRADAY", leg(), leg(), leg());
+        three.legs.leg3 = None;
+        assert!(three.validate().is_err());
+    }
+
+    #[test]
+    fn validates_the_product_type_and_legs() {
+        let leg = || OrderLeg::limit(CALL, 75, 1, 100.0);
+        let error = MultiLegOrderRequest::two_leg("CNC", leg(), leg()).validate().unwrap_err();
+        assert!(error.contains("INTRADAY or MARGIN"));
+
+        let mut market = leg();
+        market.order_type = 2;
+        assert!(MultiLegOrderRequest::two_leg("MARGIN", leg(), market).validate().is_err());
+        assert!(MultiLegOrderRequest::two_leg("MARGIN", leg(), OrderLeg::limit(PUT, 0, 1, 100.0)).validate().is_err());
+        assert!(MultiLegOrderRequest::two_leg("MARGIN", leg(), OrderLeg::limit(PUT, 75, 1, 0.0)).validate().is_err());
+    }
 }
@@ -0,0 +1,827 @@
+use crate::dataapi::DataApi;
+use crate::error::FyersError;
+use crate::models::market_data::fyers_v1;
+use crate::models::orders::{ModifyOrderRequest, SingleOrderRequest, SingleOrderResponse};
+use crate::models::transaction::{NetPosition, Order, Overall, Trade};
+use crate::models::user::FundLimit;
+use crate::models::{FundsResponse, OrdersResponse, PositionsResponse, TradesResponse};
+use crate::token_store::unix_now;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+// Order status codes, as reported by /orders
+const STATUS_CANCELLED: i64 = 1;
+const STATUS_TRADED: i64 = 2;
+const STATUS_REJECTED: i64 = 5;
+const STATUS_PENDING: i64 = 6;
+
+// Order types
+const LIMIT: i64 = 1;
+const MARKET: i64 = 2;
+const STOP_MARKET: i64 = 3;
+const STOP_LIMIT: i64 = 4;
+
+// IST offset from UTC, in seconds
+const IST_OFFSET: i64 = 19_800;
+
+/// # Description
+/// A paper trading broker. It accepts the same [SingleOrderRequest] values as
+/// [Order](crate::orders::Order), fills them against prices fed from the market data websocket or
+/// from quotes, and keeps a simulated order book, trade book, positions and funds that are
+/// returned in the same shapes as the live API.
+///
+/// Fill rules:
+/// * Market orders fill at the next price, adjusted by the slippage.
+/// * Limit orders fill once the price reaches the limit, at the limit or better.
+/// * SL-M orders trigger when the price crosses the stop and then fill like market orders.
+/// * SL-L orders trigger when the price crosses the stop and then rest as limit orders.
+///
+/// Orders fill in full. An order that would take the used margin (the cost of open positions)
+/// above the available balance is rejected when it would fill.
+#[derive(Debug)]
+pub struct PaperBroker {
+    state: Mutex<PaperState>,
+    slippage: f64,
+    price_divisor: f64,
+}
+
+#[derive(Debug)]
+struct PaperState {
+    initial_capital: f64,
+    next_order: u64,
+    next_trade: u64,
+    orders: Vec<PaperOrder>,
+    trades: Vec<Trade>,
+    positions: Vec<PaperPosition>,
+    last_prices: HashMap<String, f64>,
+}
+
+#[derive(Debug)]
+struct PaperOrder {
+    order: Order,
+    // Whether the stop of an SL-M or SL-L order has been crossed
+    triggered: bool,
+}
+
+#[derive(Debug, Default)]
+struct PaperPosition {
+    symbol: String,
+    product_type: String,
+    buy_qty: i64,
+    buy_value: f64,
+    sell_qty: i64,
+    sell_value: f64,
+    net_qty: i64,
+    net_avg: f64,
+    realized: f64,
+}
+
+impl PaperPosition {
+    fn apply_fill(&mut self, side: i64, qty: i64, price: f64) {
+        if side == 1 {
+            self.buy_qty += qty;
+            self.buy_value += qty as f64 * price;
+        } else {
+            self.sell_qty += qty;
+            self.sell_value += qty as f64 * price;
+        }
+
+        self.realized += apply_average_cost(&mut self.net_qty, &mut self.net_avg, side, qty, price);
+    }
+
+    fn unrealized(&self, ltp: f64) -> f64 {
+        (ltp - self.net_avg) * self.net_qty as f64
+    }
+}
+
+impl PaperState {
+    fn used_margin(&self) -> f64 {
+        self.positions
+            .iter()
+            .map(|position| position.net_qty.abs() as f64 * position.net_avg)
+            .sum()
+    }
+
+    fn realized(&self) -> f64 {
+        self.positions.iter().map(|position| position.realized).sum()
+    }
+
+    fn available(&self) -> f64 {
+        self.initial_capital + self.realized() - self.used_margin()
+    }
+
+    fn position_mut(&mut self, symbol: &str, product_type: &str) -> &mut PaperPosition {
+        let index = match self
+            .positions
+            .iter()
+            .position(|p| p.symbol == symbol && p.product_type == product_type)
+        {
+            Some(index) => index,
+            None => {
+                self.positions.push(PaperPosition {
+                    symbol: symbol.to_string(),
+                    product_type: product_type.to_string(),
+                    ..Default::default()
+                });
+                self.positions.len() - 1
+            }
+        };
+        &mut self.positions[index]
+    }
+
+    fn order_mut(&mut self, id: &str) -> Result<&mut PaperOrder, FyersError> {
+        self.orders
+            .iter_mut()
+            .find(|paper| paper.order.id == id)
+            .ok_or_else(|| FyersError::InvalidRequest(format!("No order with id {}", id)))
+    }
+}
+
+impl PaperBroker {
+    /// # Description
+    /// Create a paper broker with the given starting balance
+    ///
+    /// # Arguments
+    /// * `initial_capital` - The balance available at the start, in rupees
+    pub fn new(initial_capital: f64) -> Self {
+        Self {
+            state: Mutex::new(PaperState {
+                initial_capital,
+                next_order: 1,
+                next_trade: 1,
+                orders: Vec::new(),
+                trades: Vec::new(),
+                positions: Vec::new(),
+                last_prices: HashMap::new(),
+            }),
+            slippage: 0.0,
+            price_divisor: 100.0,
+        }
+    }
+
+    /// # Description
+    /// Set the slippage applied to market and SL-M fills, against the order
+    ///
+    /// # Arguments
+    /// * `slippage` - Slippage as a fraction of the price (e.g. 0.0005 for 5 basis points)
+    pub fn with_slippage(mut self, slippage: f64) -> Self {
+        self.slippage = slippage;
+        self
+    }
+
+    /// # Description
+    /// Set the divisor websocket prices are scaled by (see
+    /// [CandleBuilder::with_price_divisor](crate::websocket::CandleBuilder::with_price_divisor))
+    pub fn with_price_divisor(mut self, divisor: f64) -> Self {
+        self.price_divisor = divisor;
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, PaperState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// # Description
+    /// Place a simulated order. The order fills immediately if the last price of the symbol is
+    /// known and satisfies it, otherwise it stays pending until a price that does arrives.
+    ///
+    /// # Arguments
+    /// * `order` - The order to place, as a SingleOrderRequest
+    pub fn single_order(&self, order: &SingleOrderRequest) -> Result<SingleOrderResponse, FyersError> {
+        validate(order.order_type, order.qty, order.limit_price, order.stop_price)?;
+        if order.side != 1 && order.side != -1 {
+            return Err(FyersError::InvalidRequest("side must be 1 (buy) or -1 (sell)".to_string()));
+        }
+
+        let mut state = self.lock();
+        let id = format!("PAPER-{}", state.next_order);
+        state.next_order += 1;
+
+        let (exchange, segment) = exchange_and_segment(&order.symbol);
+        let sl_no = state.orders.len() as i64 + 1;
+        state.orders.push(PaperOrder {
+            order: Order {
+                id: id.clone(),
+                exch_ord_id: String::new(),
+                symbol: order.symbol.clone(),
+                qty: order.qty,
+                remaining_quantity: order.qty,
+                filled_qty: 0,
+                status: STATUS_PENDING,
+                sl_no,
+                message: String::new(),
+                segment,
+                limit_price: order.limit_price,
+                stop_price: order.stop_price,
+                product_type: order.product_type.clone(),
+                in_type: order.order_type,
+                side: order.side,
+                disclosed_qty: order.disclosed_qty,
+                order_validity: order.validity.clone(),
+                order_date_time: format_time(unix_now()),
+                parent_id: String::new(),
+                traded_price: 0.0,
+                source: "PAPER".to_string(),
+                fytoken: String::new(),
+                offline_order: order.offline_order,
+                pan: String::new(),
+                client_id: String::new(),
+                exchange,
+                instrument: 0,
+                disclose_qty: order.disclosed_qty,
+                order_tag: order.order_tag.clone().unwrap_or_default(),
+            },
+            triggered: false,
+        });
+
+        if let Some(&price) = state.last_prices.get(&order.symbol) {
+            self.match_orders(&mut state, &order.symbol, price);
+        }
+
+        Ok(SingleOrderResponse {
+            s: "ok".to_string(),
+            code: 1101,
+            message: "Order submitted successfully".to_string(),
+            id,
+        })
+    }
+
+    /// # Description
+    /// Modify a pending simulated order
+    ///
+    /// # Arguments
+    /// * `request` - The modification, as a ModifyOrderRequest
+    pub fn modify_order(&self, request: &ModifyOrderRequest) -> Result<SingleOrderResponse, FyersError> {
+        let mut state = self.lock();
+        let paper = state.order_mut(&request.id)?;
+        if paper.order.status != STATUS_PENDING {
+            return Err(FyersError::InvalidRequest(format!("Order {} is not pending", request.id)));
+        }
+
+        let order_type = request.order_type.unwrap_or(paper.order.in_type);
+        let qty = request.qty.unwrap_or(paper.order.qty);
+        let limit_price = request.limit_price.unwrap_or(paper.order.limit_price);
+        let stop_price = request.stop_price.unwrap_or(paper.order.stop_price);
+        validate(order_type, qty, limit_price, stop_price)?;
+
+        paper.order.in_type = order_type;
+        paper.order.qty = qty;
+        paper.order.remaining_quantity = qty;
+        paper.order.limit_price = limit_price;
+        paper.order.stop_price = stop_price;
+        let symbol = paper.order.symbol.clone();
+
+        if let Some(&price) = state.last_prices.get(&symbol) {
+            self.match_orders(&mut state, &symbol, price);
+        }
+
+        Ok(SingleOrderResponse {
+            s: "ok".to_string(),
+            code: 1102,
+            message: "Successfully modified order".to_string(),
+            id: request.id.clone(),
+        })
+    }
+
+    /// # Description
+    /// Cancel a pending simulated order
+    ///
+    /// # Arguments
+    /// * `id` - The id of the order
+    pub fn cancel_order(&self, id: &str) -> Result<SingleOrderResponse, FyersError> {
+        let mut state = self.lock();
+        let paper = state.order_mut(id)?;
+        if paper.order.status != STATUS_PENDING {
+            return Err(FyersError::InvalidRequest(format!("Order {} is not pending", id)));
+        }
+        paper.order.status = STATUS_CANCELLED;
+        paper.order.message = "Cancelled".to_string();
+
+        Ok(SingleOrderResponse {
+            s: "ok".to_string(),
+            code: 1103,
+            message: "Successfully cancelled order".to_string(),
+            id: id.to_string(),
+        })
+    }
+
+    /// # Description
+    /// Feed the last traded price of a symbol, filling the pending orders it satisfies. Returns
+    /// the resulting trades.
+    ///
+    /// # Arguments
+    /// * `symbol` - The symbol ticker
+    /// * `price` - The last traded price
+    pub fn on_price(&self, symbol: &str, price: f64) -> Vec<Trade> {
+        let mut state = self.lock();
+        state.last_prices.insert(symbol.to_string(), price);
+        self.match_orders(&mut state, symbol, price)
+    }
+
+    /// # Description
+    /// Feed a decoded websocket message. Feeds without a last traded price are ignored. Returns
+    /// the resulting trades.
+    ///
+    /// # Arguments
+    /// * `message` - A message received from [MarketDataSocket::listen](crate::websocket::MarketDataSocket::listen)
+    pub fn on_message(&self, message: &fyers_v1::SocketMessage) -> Vec<Trade> {
+        let mut trades = Vec::new();
+        for (key, feed) in &message.feeds {
+            let Some(ltp) = feed.quote.as_ref().and_then(|quote| quote.ltp) else {
+                continue;
+            };
+            let symbol = if feed.ticker.is_empty() { key } else { &feed.ticker };
+            trades.extend(self.on_price(symbol, ltp as f64 / self.price_divisor));
+        }
+        trades
+    }
+
+    /// # Description
+    /// Fetch quotes for every symbol with a pending order or an open position and feed their last
+    /// prices. Symbols whose quote fails are skipped. Returns the resulting trades.
+    ///
+    /// # Arguments
+    /// * `data` - The client to fetch quotes with
+    pub async fn update_from_quotes(&self, data: &DataApi) -> Vec<Trade> {
+        let symbols: Vec<String> = {
+            let state = self.lock();
+            let mut symbols: Vec<String> = state
+                .orders
+                .iter()
+                .filter(|paper| paper.order.status == STATUS_PENDING)
+                .map(|paper| paper.order.symbol.clone())
+                .chain(
+                    state
+                        .positions
+                        .iter()
+                        .filter(|position| position.net_qty != 0)
+                        .map(|position| position.symbol.clone()),
+                )
+                .collect();
+            symbols.sort();
+            symbols.dedup();
+            symbols
+        };
+        if symbols.is_empty() {
+            return Vec::new();
+        }
+
+        let symbols: Vec<&str> = symbols.iter().map(String::as_str).collect();
+        let quotes = data.get_market_quotes_batched(&symbols).await;
+        let mut trades = Vec::new();
+        for (symbol, quote) in quotes {
+            if let Ok(quote) = quote {
+                trades.extend(self.on_price(&symbol, quote.lp));
+            }
+        }
+        trades
+    }
+
+    /// # Description
+    /// The simulated order book, in the shape of [Transaction::get_orders](crate::transaction::Transaction::get_orders)
+    pub fn get_orders(&self) -> OrdersResponse {
+        let state = self.lock();
+        OrdersResponse {
+            s: "ok".to_string(),
+            code: 200,
+            message: String::new(),
+            order_book: state.orders.iter().map(|paper| paper.order.clone()).collect(),
+        }
+    }
+
+    /// # Description
+    /// The simulated trade book, in the shape of [Transaction::get_trades](crate::transaction::Transaction::get_trades)
+    pub fn get_trades(&self) -> TradesResponse {
+        let state = self.lock();
+        TradesResponse {
+            s: "ok".to_string(),
+            code: 200,
+            message: String::new(),
+            trade_book: state.trades.clone(),
+        }
+    }
+
+    /// # Description
+    /// The simulated positions, marked to the last known prices, in the shape of
+    /// [Transaction::get_positions](crate::transaction::Transaction::get_positions)
+    pub fn get_positions(&self) -> PositionsResponse {
+        let state = self.lock();
+        let mut overall = Overall {
+            count_total: 0,
+            count_open: 0,
+            pl_total: 0.0,
+            pl_realized: 0.0,
+            pl_unrealized: 0.0,
+        };
+
+        let net_positions = state
+            .positions
+            .iter()
+            .enumerate()
+            .map(|(index, position)| {
+                let ltp = state
+                    .last_prices
+                    .get(&position.symbol)
+                    .copied()
+                    .unwrap_or(position.net_avg);
+                let unrealized = position.unrealized(ltp);
+                overall.count_total += 1;
+                if position.net_qty != 0 {
+                    overall.count_open += 1;
+                }
+                overall.pl_realized += position.realized;
+                overall.pl_unrealized += unrealized;
+
+                let (exchange, segment) = exchange_and_segment(&position.symbol);
+                NetPosition {
+                    symbol: position.symbol.clone(),
+                    id: format!("{}-{}", position.symbol, position.product_type),
+                    buy_avg: average(position.buy_value, position.buy_qty),
+                    buy_qty: position.buy_qty,
+                    sell_avg: average(position.sell_value, position.sell_qty),
+                    sell_qty: position.sell_qty,
+                    net_avg: position.net_avg,
+                    net_qty: position.net_qty,
+                    side: position.net_qty.signum(),
+                    qty: position.net_qty.abs(),
+                    product_type: position.product_type.clone(),
+                    realized_profit: position.realized,
+                    pl: position.realized + unrealized,
+                    cross_currency: String::new(),
+                    rbi_ref_rate: 1.0,
+                    qty_multi_com: 1.0,
+                    segment,
+                    exchange,
+                    sl_no: index as i64 + 1,
+                    ltp,
+                    fy_token: String::new(),
+                    cf_buy_qty: 0,
+                    cf_sell_qty: 0,
+                    day_buy_qty: position.buy_qty,
+                    day_sell_qty: position.sell_qty,
+                }
+            })
+            .collect();
+        overall.pl_total = overall.pl_realized + overall.pl_unrealized;
+
+        PositionsResponse {
+            s: "ok".to_string(),
+            code: 200,
+            message: String::new(),
+            net_positions,
+            overall,
+        }
+    }
+
+    /// # Description
+    /// The simulated funds, in the shape of [User::get_funds](crate::user::User::get_funds). All
+    /// amounts are reported against equity.
+    pub fn get_funds(&self) -> FundsResponse {
+        let state = self.lock();
+        let realized = state.realized();
+        let used = state.used_margin();
+        let entry = |id: i64, title: &str, amount: f64| FundLimit {
+            id,
+            title: title.to_string(),
+            equity_amount: amount,
+            commodity_amount: 0.0,
+        };
+
+        FundsResponse {
+            s: "ok".to_string(),
+            code: 200,
+            message: String::new(),
+            fund_limit: vec![
+                entry(1, "Total Balance", state.initial_capital + realized),
+                entry(2, "Utilized Amount", used),
+                entry(3, "Clear Balance", state.initial_capital + realized),
+                entry(4, "Realized Profit and Loss", realized),
+                entry(9, "Limit at start of the day", state.initial_capital),
+                entry(10, "Available Balance", state.available()),
+            ],
+        }
+    }
+
+    // Fill every pending order of `symbol` that `price` satisfies
+    fn match_orders(&self, state: &mut PaperState, symbol: &str, price: f64) -> Vec<Trade> {
+        let mut trades = Vec::new();
+        for index in 0..state.orders.len() {
+            let paper = &mut state.orders[index];
+            if paper.order.symbol != symbol || paper.order.status != STATUS_PENDING {
+                continue;
+            }
+            let Some(fill_price) = fill_price(paper, price, self.slippage) else {
+                continue;
+            };
+
+            let (side, qty) = (paper.order.side, paper.order.qty);
+            let product_type = paper.order.product_type.clone();
+
+            // Only the part of the fill that adds to the position needs margin
+            let net_qty = state
+                .positions
+                .iter()
+                .find(|p| p.symbol == symbol && p.product_type == product_type)
+                .map_or(0, |p| p.net_qty);
+            let opening_qty = if net_qty == 0 || net_qty.signum() == side {
+                qty
+            } else {
+                (qty - net_qty.abs()).max(0)
+            };
+            if opening_qty as f64 * fill_price > state.available() {
+                let paper = &mut state.orders[index];
+                paper.order.status = STATUS_REJECTED;
+                paper.order.message = "Insufficient funds".to_string();
+                continue;
+            }
+
+            state.position_mut(symbol, &product_type).apply_fill(side, qty, fill_price);
+
+            let trade_number = format!("PAPER-T{}", state.next_trade);
+            state.next_trade += 1;
+            let row = state.trades.len() as i64 + 1;
+            let paper = &mut state.orders[index];
+            paper.order.status = STATUS_TRADED;
+            paper.order.filled_qty = qty;
+            paper.order.remaining_quantity = 0;
+            paper.order.traded_price = fill_price;
+            paper.order.message = "Traded".to_string();
+
+            let trade = Trade {
+                symbol: symbol.to_string(),
+                row,
+                order_date_time: format_time(unix_now()),
+                order_number: paper.order.id.clone(),
+                trade_number,
+                trade_price: fill_price,
+                trade_value: fill_price * qty as f64,
+                traded_qty: qty,
+                side,
+                product_type,
+                exchange_order_no: String::new(),
+                segment: paper.order.segment,
+                exchange: paper.order.exchange,
+                fy_token: String::new(),
+                order_tag: paper.order.order_tag.clone(),
+            };
+            state.trades.push(trade.clone());
+            trades.push(trade);
+        }
+        trades
+    }
+}
+
+// The price an order fills at when the market trades at `price`, or `None` if it does not fill
+fn fill_price(paper: &mut PaperOrder, price: f64, slippage: f64) -> Option<f64> {
+    let order = &paper.order;
+    let buy = order.side == 1;
+    let with_slippage = if buy { price * (1.0 + slippage) } else { price * (1.0 - slippage) };
+    let crosses_stop = if buy { price >= order.stop_price } else { price <= order.stop_price };
+    let crosses_limit = if buy { price <= order.limit_price } else { price >= order.limit_price };
+    let limit_fill = if buy { price.min(order.limit_price) } else { price.max(order.limit_price) };
+
+    match order.in_type {
+        MARKET => Some(with_slippage),
+        LIMIT => crosses_limit.then_some(limit_fill),
+        STOP_MARKET => {
+            paper.triggered |= crosses_stop;
+            paper.triggered.then_some(with_slippage)
+        }
+        STOP_LIMIT => {
+            paper.triggered |= crosses_stop;
+            (paper.triggered && crosses_limit).then_some(limit_fill)
+        }
+        _ => None,
+    }
+}
+
+fn validate(order_type: i64, qty: i64, limit_price: f64, stop_price: f64) -> Result<(), FyersError> {
+    if qty <= 0 {
+        return Err(FyersError::InvalidRequest("qty must be positive".to_string()));
+    }
+    match order_type {
+        MARKET => Ok(()),
+        LIMIT if limit_price > 0.0 => Ok(()),
+        STOP_MARKET if stop_price > 0.0 => Ok(()),
+        STOP_LIMIT if limit_price > 0.0 && stop_price > 0.0 => Ok(()),
+        LIMIT | STOP_MARKET | STOP_LIMIT => Err(FyersError::InvalidRequest(
+            "limit orders need a limitPrice and stop orders need a stopPrice".to_string(),
+        )),
+        _ => Err(FyersError::InvalidRequest(format!("Unknown order type {}", order_type))),
+    }
+}
+
+fn average(value: f64, qty: i64) -> f64 {
+    if qty == 0 {
+        0.0
+    } else {
+        value / qty as f64
+    }
+}
+
+/// # Description
+/// Apply a fill to a position kept at average cost: quantity that adds to the position moves the
+/// average price, quantity that reduces it realizes P&L against the average price, and the
+/// remainder of a reversal opens a new position at the fill price. Returns the realized P&L, in
+/// price points times quantity.
+pub(crate) fn apply_average_cost(net_qty: &mut i64, net_avg: &mut f64, side: i64, qty: i64, price: f64) -> f64 {
+    let signed = side * qty;
+    if *net_qty == 0 || net_qty.signum() == side {
+        let total = net_qty.abs() + qty;
+        *net_avg = (*net_avg * net_qty.abs() as f64 + price * qty as f64) / total as f64;
+        *net_qty += signed;
+        return 0.0;
+    }
+
+    let closed = qty.min(net_qty.abs());
+    let realized = (price - *net_avg) * closed as f64 * net_qty.signum() as f64;
+    *net_qty += signed;
+    if *net_qty == 0 {
+        *net_avg = 0.0;
+    } else if net_qty.signum() == side {
+        *net_avg = price;
+    }
+    realized
+}
+
+/// Currency pairs traded as derivatives on NSE and BSE
+const CURRENCY_PAIRS: [&str; 7] = ["USDINR", "EURINR", "GBPINR", "JPYINR", "EURUSD", "GBPUSD", "USDJPY"];
+
+/// # Description
+/// The exchange and segment codes of a symbol such as "NSE:SBIN-EQ", as used in the order book,
+/// or `None` when the exchange prefix is missing or unknown
+pub(crate) fn segment_of(symbol: &str) -> Option<(i64, i64)> {
+    let (exchange, ticker) = symbol.split_once(':')?;
+    let derivative = is_derivative(ticker);
+    let currency = derivative && CURRENCY_PAIRS.iter().any(|pair| ticker.starts_with(pair));
+    let exchange = match exchange {
+        "NSE" => 10,
+        "MCX" => return Some((11, 20)),
+        "BSE" => 12,
+        _ => return None,
+    };
+    let segment = if currency {
+        12
+    } else if derivative {
+        11
+    } else {
+        10
+    };
+    Some((exchange, segment))
+}
+
+/// The exchange and segment codes of a symbol, taking a symbol without a known exchange prefix
+/// to be on NSE
+pub(crate) fn exchange_and_segment(symbol: &str) -> (i64, i64) {
+    segment_of(symbol)
+        .or_else(|| {
+            let ticker = symbol.split_once(':').map_or(symbol, |(_, ticker)| ticker);
+            segment_of(&format!("NSE:{}", ticker))
+        })
+        .unwrap_or((10, 10))
+}
+
+fn is_derivative(ticker: &str) -> bool {
+    ticker.ends_with("FUT") || ticker.ends_with("CE") || ticker.ends_with("PE")
+}
+
+/// Format epoch seconds as an IST order book time, e.g. "05-Sep-2025 10:15:23"
+fn format_time(epoch: i64) -> String {
+    const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+    let local = epoch + IST_OFFSET;
+    let (days, seconds) = (local.div_euclid(86_400), local.rem_euclid(86_400));
+
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:02}-{}-{} {:02}:{:02}:{:02}",
+        day,
+        MONTHS[(month - 1) as usize],
+        year,
+        seconds / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(symbol: &str, order_type: i64, side: i64, qty: i64, limit_price: f64, stop_price: f64) -> SingleOrderRequest {
+        SingleOrderRequest {
+            symbol: symbol.to_string(),
+            qty,
+            order_type,
+            side,
+            product_type: "INTRADAY".to_string(),
+            validity: "DAY".to_string(),
+            offline_order: false,
+            limit_price,
+            stop_price,
+            disclosed_qty: 0,
+            stop_loss: 0.0,
+            take_profit: 0.0,
+            order_tag: None,
+        }
+    }
+
+    const SBIN: &str = "NSE:SBIN-EQ";
+
+    #[test]
+    fn market_orders_fill_at_the_next_price_with_slippage() {
+        let broker = PaperBroker::new(100_000.0).with_slippage(0.001);
+        broker.single_order(&order(SBIN, MARKET, 1, 10, 0.0, 0.0)).unwrap();
+        let trades = broker.on_price(SBIN, 800.0);
+
+        assert_eq!(trades.len(), 1);
+        assert!((trades[0].trade_price - 800.8).abs() < 1e-9);
+        assert_eq!(broker.get_orders().order_book[0].status, STATUS_TRADED);
+    }
+
+    #[test]
+    fn limit_orders_fill_at_the_limit_or_better() {
+        let broker = PaperBroker::new(100_000.0);
+        broker.single_order(&order(SBIN, LIMIT, 1, 10, 795.0, 0.0)).unwrap();
+        assert!(broker.on_price(SBIN, 800.0).is_empty());
+        let trades = broker.on_price(SBIN, 790.0);
+        assert_eq!(trades[0].trade_price, 790.0);
+    }
+
+    #[test]
+    fn stop_orders_trigger_on_the_stop() {
+        let broker = PaperBroker::new(100_000.0);
+        broker.on_price(SBIN, 800.0);
+        broker.single_order(&order(SBIN, STOP_MARKET, -1, 10, 0.0, 790.0)).unwrap();
+        assert!(broker.on_price(SBIN, 795.0).is_empty());
+        assert_eq!(broker.on_price(SBIN, 789.0)[0].trade_price, 789.0);
+
+        // SL-L: triggered at 810, then rests as a buy limit at 812
+        broker.single_order(&order(SBIN, STOP_LIMIT, 1, 10, 812.0, 810.0)).unwrap();
+        assert!(broker.on_price(SBIN, 809.0).is_empty());
+        assert!(broker.on_price(SBIN, 815.0).is_empty());
+        assert_eq!(broker.on_price(SBIN, 811.0)[0].trade_price, 811.0);
+    }
+
+    #[test]
+    fn orders_above_the_available_balance_are_rejected() {
+        let broker = PaperBroker::new(5_000.0);
+        broker.single_order(&order(SBIN, MARKET, 1, 10, 0.0, 0.0)).unwrap();
+        assert!(broker.on_price(SBIN, 800.0).is_empty());
+        let rejected = &broker.get_orders().order_book[0];
+        assert_eq!(rejected.status, STATUS_REJECTED);
+        assert_eq!(rejected.message, "Insufficient funds");
+    }
+
+    #[test]
+    fn positions_and_funds_follow_the_fills() {
+        let broker = PaperBroker::new(100_000.0);
+        broker.on_price(SBIN, 800.0);
+        broker.single_order(&order(SBIN, MARKET, 1, 10, 0.0, 0.0)).unwrap();
+        broker.on_price(SBIN, 820.0);
+        broker.single_order(&order(SBIN, MARKET, -1, 4, 0.0, 0.0)).unwrap();
+
+        let positions = broker.get_positions();
+        let position = &positions.net_positions[0];
+        assert_eq!(position.net_qty, 6);
+        assert_eq!(position.net_avg, 800.0);
+        assert_eq!(position.realized_profit, 80.0);
+        assert_eq!(positions.overall.pl_unrealized, 120.0);
+
+        let funds = broker.get_funds().funds();
+        assert_eq!(funds.equity.utilized_margin, 4_800.0);
+        assert_eq!(funds.equity.available_balance, 100_000.0 + 80.0 - 4_800.0);
+    }
+
+    #[test]
+    fn average_cost_handles_reversals() {
+        let (mut net_qty, mut net_avg) = (0, 0.0);
+        assert_eq!(apply_average_cost(&mut net_qty, &mut net_avg, 1, 10, 100.0), 0.0);
+        assert_eq!(apply_average_cost(&mut net_qty, &mut net_avg, 1, 10, 110.0), 0.0);
+        assert_eq!((net_qty, net_avg), (20, 105.0));
+        assert_eq!(apply_average_cost(&mut net_qty, &mut net_avg, -1, 25, 120.0), 300.0);
+        assert_eq!((net_qty, net_avg), (-5, 120.0));
+    }
+
+    #[test]
+    fn classifies_segments() {
+        assert_eq!(exchange_and_segment("NSE:SBIN-EQ"), (10, 10));
+        assert_eq!(exchange_and_segment("NSE:NIFTY25SEPFUT"), (10, 11));
+        assert_eq!(exchange_and_segment("NSE:NIFTY25SEP25000CE"), (10, 11));
+        assert_eq!(exchange_and_segment("NSE:USDINR25SEPFUT"), (10, 12));
+        assert_eq!(exchange_and_segment("NSE:USDINR25SEP83.5PE"), (10, 12));
+        assert_eq!(exchange_and_segment("BSE:SENSEX25SEPFUT"), (12, 11));
+        assert_eq!(exchange_and_segment("MCX:CRUDEOIL25SEPFUT"), (11, 20));
+        assert_eq!(exchange_and_segment("SBIN-EQ"), (10, 10));
+        assert_eq!(segment_of("NFO:NIFTY25SEPFUT"), None);
+    }
+}
@@ -0,0 +1,381 @@
+use crate::error::FyersError;
+use crate::models::market_data::fyers_v1;
+use crate::models::transaction::{Order, Trade};
+use crate::models::PositionsResponse;
+use crate::paper::apply_average_cost;
+use crate::transaction::Transaction;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Realized, unrealized and total P&L
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PnlTotals {
+    pub realized: f64,
+    pub unrealized: f64,
+    pub total: f64,
+}
+
+impl PnlTotals {
+    fn add(&mut self, realized: f64, unrealized: f64) {
+        self.realized += realized;
+        self.unrealized += unrealized;
+        self.total = self.realized + self.unrealized;
+    }
+}
+
+/// A point of the mark-to-market history
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MtmPoint {
+    /// Epoch milliseconds
+    pub timestamp: i64,
+    pub pnl: PnlTotals,
+}
+
+/// The live P&L of a position, kept per symbol and product type
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionPnl {
+    pub symbol: String,
+    pub product_type: String,
+    /// Signed net quantity
+    pub net_qty: i64,
+    /// Average price of the open quantity
+    pub net_avg: f64,
+    pub realized: f64,
+    /// Last traded price
+    pub ltp: f64,
+    /// Value of one unit of quantity per rupee of price (`qtyMulti_com`, 1 outside commodities)
+    pub multiplier: f64,
+}
+
+impl PositionPnl {
+    fn new(symbol: &str, product_type: &str) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            product_type: product_type.to_string(),
+            net_qty: 0,
+            net_avg: 0.0,
+            realized: 0.0,
+            ltp: 0.0,
+            multiplier: 1.0,
+        }
+    }
+
+    pub fn unrealized(&self) -> f64 {
+        (self.ltp - self.net_avg) * self.net_qty as f64 * self.multiplier
+    }
+
+    pub fn total(&self) -> f64 {
+        self.realized + self.unrealized()
+    }
+
+    // Apply a fill at average cost: closing quantity realizes P&L against the average price
+    fn apply_fill(&mut self, side: i64, qty: i64, price: f64) {
+        self.realized += apply_average_cost(&mut self.net_qty, &mut self.net_avg, side, qty, price) * self.multiplier;
+    }
+}
+
+// Reads of the trade book and positions in PnlEngine::seed_from before seeding with a trade
+// book that still changed
+const MAX_SEED_ATTEMPTS: usize = 3;
+
+fn trade_numbers(trades: &[Trade]) -> HashSet<String> {
+    trades.iter().map(|trade| trade.trade_number.clone()).collect()
+}
+
+/// # Description
+/// A live P&L engine. Seed it from the positions, then keep it up to date with prices from the
+/// market data websocket ([PnlEngine::on_message]) and fills from the trade book
+/// ([PnlEngine::sync_trades]) or the order websocket ([PnlEngine::on_order_update]). Use only one
+/// of the two as the source of fills, or fills are counted twice.
+#[derive(Debug, Clone)]
+pub struct PnlEngine {
+    positions: BTreeMap<(String, String), PositionPnl>,
+    // Trade numbers already applied, so the trade book can be read repeatedly
+    seen_trades: HashSet<String>,
+    // Filled quantity and average price of each order already applied
+    order_fills: HashMap<String, (i64, f64)>,
+    history: Vec<MtmPoint>,
+    mtm_interval: Option<Duration>,
+    last_mtm: Option<Instant>,
+    price_divisor: f64,
+}
+
+impl Default for PnlEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PnlEngine {
+    pub fn new() -> Self {
+        Self {
+            positions: BTreeMap::new(),
+            seen_trades: HashSet::new(),
+            order_fills: HashMap::new(),
+            history: Vec::new(),
+            mtm_interval: None,
+            last_mtm: None,
+            price_divisor: 100.0,
+        }
+    }
+
+    /// # Description
+    /// Record a point of the MTM history on price and fill updates, at most once per `interval`.
+    /// Without it points are only recorded by [PnlEngine::record_mtm].
+    pub fn with_mtm_interval(mut self, interval: Duration) -> Self {
+        self.mtm_interval = Some(interval);
+        self
+    }
+
+    /// # Description
+    /// Set the divisor websocket prices are scaled by (see
+    /// [CandleBuilder::with_price_divisor](crate::websocket::CandleBuilder::with_price_divisor))
+    pub fn with_price_divisor(mut self, divisor: f64) -> Self {
+        self.price_divisor = divisor;
+        self
+    }
+
+    /// # Description
+    /// Replace the positions with the ones returned by
+    /// [Transaction::get_positions](crate::transaction::Transaction::get_positions)
+    pub fn seed(&mut self, positions: &PositionsResponse) {
+        self.positions.clear();
+        for position in &positions.net_positions {
+            let mut pnl = PositionPnl::new(&position.symbol, &position.product_type);
+            pnl.net_qty = position.net_qty;
+            pnl.net_avg = position.net_avg;
+            pnl.realized = position.realized_profit;
+            pnl.ltp = position.ltp;
+            if position.qty_multi_com > 0.0 {
+                pnl.multiplier = position.qty_multi_com;
+            }
+            self.positions
+                .insert((position.symbol.clone(), position.product_type.clone()), pnl);
+        }
+    }
+
+    /// # Description
+    /// Seed from the current positions. The current trade book and order fills are marked as
+    /// applied, since the positions already include them. The trade book is read before and after
+    /// the positions and the reads are retried while it changes in between, so a fill landing
+    /// during seeding is neither missed nor counted twice. If it still changes on the last
+    /// attempt, the later trade book is used, since the positions were read before it.
+    ///
+    /// # Arguments
+    /// * `transaction` - The client to read the positions, trade book and order book with
+    pub async fn seed_from(&mut self, transaction: &Transaction) -> Result<(), FyersError> {
+        let mut attempts = 0;
+        let (trades, orders, positions) = loop {
+            attempts += 1;
+            let trades = trade_numbers(&transaction.get_trades(None).await?.trade_book);
+            let orders = transaction.get_orders(None, None).await?;
+            let positions = transaction.get_positions().await?;
+            let after = trade_numbers(&transaction.get_trades(None).await?.trade_book);
+            if after == trades || attempts == MAX_SEED_ATTEMPTS {
+                break (after, orders, positions);
+            }
+        };
+        self.seed(&positions);
+        self.seen_trades = trades;
+        self.order_fills = orders
+            .order_book
+            .iter()
+            .map(|order| (order.id.clone(), (order.filled_qty, order.traded_price)))
+            .collect();
+        self.record_mtm();
+        Ok(())
+    }
+
+    /// # Description
+    /// Update the last traded price of a symbol, across its product types
+    pub fn on_price(&mut self, symbol: &str, ltp: f64) {
+        for position in self.positions.values_mut().filter(|p| p.symbol == symbol) {
+            position.ltp = ltp;
+        }
+        self.maybe_record_mtm();
+    }
+
+    /// # Description
+    /// Feed a decoded websocket message. Feeds without a last traded price are ignored.
+    ///
+    /// # Arguments
+    /// * `message` - A message received from [MarketDataSocket::listen](crate::websocket::MarketDataSocket::listen)
+    pub fn on_message(&mut self, message: &fyers_v1::SocketMessage) {
+        for (key, feed) in &message.feeds {
+            let Some(ltp) = feed.quote.as_ref().and_then(|quote| quote.ltp) else {
+                continue;
+            };
+            let symbol = if feed.ticker.is_empty() { key } else { &feed.ticker };
+            self.on_price(symbol, ltp as f64 / self.price_divisor);
+        }
+    }
+
+    /// # Description
+    /// Apply a fill from the trade book. Trades that were already applied are skipped.
+    pub fn on_trade(&mut self, trade: &Trade) {
+        if !self.seen_trades.insert(trade.trade_number.clone()) {
+            return;
+        }
+        self.apply_fill(&trade.symbol, &trade.product_type, trade.side, trade.traded_qty, trade.trade_price);
+    }
+
+    /// # Description
+    /// Apply an order book entry, e.g. from the order websocket. The part of its filled quantity
+    /// that was not applied before is applied as a fill, at the price implied by the change of
+    /// the average traded price.
+    pub fn on_order_update(&mut self, order: &Order) {
+        let (filled_before, average_before) = self.order_fills.get(&order.id).copied().unwrap_or((0, 0.0));
+        let new_qty = order.filled_qty - filled_before;
+        if new_qty <= 0 {
+            return;
+        }
+        let price = (order.traded_price * order.filled_qty as f64 - average_before * filled_before as f64) / new_qty as f64;
+        self.order_fills
+            .insert(order.id.clone(), (order.filled_qty, order.traded_price));
+        self.apply_fill(&order.symbol, &order.product_type, order.side, new_qty, price);
+    }
+
+    /// # Description
+    /// Read the trade book and apply the trades that were not applied yet
+    ///
+    /// # Arguments
+    /// * `transaction` - The client to read the trade book with
+    pub async fn sync_trades(&mut self, transaction: &Transaction) -> Result<(), FyersError> {
+        let trades = transaction.get_trades(None).await?;
+        for trade in &trades.trade_book {
+            self.on_trade(trade);
+        }
+        Ok(())
+    }
+
+    fn apply_fill(&mut self, symbol: &str, product_type: &str, side: i64, qty: i64, price: f64) {
+        let position = self
+            .positions
+            .entry((symbol.to_string(), product_type.to_string()))
+            .or_insert_with(|| PositionPnl::new(symbol, product_type));
+        position.apply_fill(side, qty, price);
+        if position.ltp == 0.0 {
+            position.ltp = price;
+        }
+        self.maybe_record_mtm();
+    }
+
+    pub fn position(&self, symbol: &str, product_type: &str) -> Option<&PositionPnl> {
+        self.positions.get(&(symbol.to_string(), product_type.to_string()))
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = &PositionPnl> {
+        self.positions.values()
+    }
+
+    /// P&L of every symbol, across product types
+    pub fn by_symbol(&self) -> BTreeMap<String, PnlTotals> {
+        self.group_by(|position| position.symbol.clone())
+    }
+
+    /// P&L of every product type, across symbols
+    pub fn by_product(&self) -> BTreeMap<String, PnlTotals> {
+        self.group_by(|position| position.product_type.clone())
+    }
+
+    /// P&L of the whole portfolio
+    pub fn total(&self) -> PnlTotals {
+        let mut totals = PnlTotals::default();
+        for position in self.positions.values() {
+            totals.add(position.realized, position.unrealized());
+        }
+        totals
+    }
+
+    fn group_by<F: Fn(&PositionPnl) -> String>(&self, key: F) -> BTreeMap<String, PnlTotals> {
+        let mut groups: BTreeMap<String, PnlTotals> = BTreeMap::new();
+        for position in self.positions.values() {
+            groups
+                .entry(key(position))
+                .or_default()
+                .add(position.realized, position.unrealized());
+        }
+        groups
+    }
+
+    /// # Description
+    /// Record the current portfolio P&L in the MTM history
+    pub fn record_mtm(&mut self) {
+        self.record_mtm_at(Instant::now());
+    }
+
+    fn record_mtm_at(&mut self, now: Instant) {
+        let point = MtmPoint {
+            timestamp: unix_millis(),
+            pnl: self.total(),
+        };
+        self.history.push(point);
+        self.last_mtm = Some(now);
+    }
+
+    fn maybe_record_mtm(&mut self) {
+        self.maybe_record_mtm_at(Instant::now());
+    }
+
+    fn maybe_record_mtm_at(&mut self, now: Instant) {
+        let Some(interval) = self.mtm_interval else { return };
+        let due = self
+            .last_mtm
+            .is_none_or(|last| now.saturating_duration_since(last) >= interval);
+        if due {
+            self.record_mtm_at(now);
+        }
+    }
+
+    /// The MTM history, oldest first
+    pub fn history(&self) -> &[MtmPoint] {
+        &self.history
+    }
+}
+
+/// The current time in epoch milliseconds
+fn unix_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_mtm_at_sub_second_intervals() {
+        let mut engine = PnlEngine::new().with_mtm_interval(Duration::from_millis(20));
+        let start = Instant::now();
+        engine.maybe_record_mtm_at(start);
+        engine.maybe_record_mtm_at(start + Duration::from_millis(10));
+        assert_eq!(engine.history().len(), 1);
+
+        engine.maybe_record_mtm_at(start + Duration::from_millis(20));
+        engine.maybe_record_mtm_at(start + Duration::from_millis(30));
+        assert_eq!(engine.history().len(), 2);
+    }
+
+    #[test]
+    fn mtm_timestamps_are_epoch_milliseconds() {
+        let before = unix_millis();
+        let mut engine = PnlEngine::new();
+        engine.record_mtm();
+        let timestamp = engine.history()[0].timestamp;
+        assert!(timestamp >= before && timestamp <= unix_millis());
+        // Epoch seconds would be around 1.7e9, milliseconds around 1.7e12
+        assert!(timestamp > 1_000_000_000_000);
+    }
+
+    #[test]
+    fn realized_pnl_scales_with_the_multiplier() {
+        let mut position = PositionPnl::new("MCX:CRUDEOIL25SEPFUT", "INTRADAY");
+        position.multiplier = 100.0;
+        position.apply_fill(1, 2, 6_000.0);
+        position.apply_fill(-1, 1, 6_010.0);
+        assert_eq!(position.realized, 1_000.0);
+        assert_eq!((position.net_qty, position.net_avg), (1, 6_000.0));
+    }
+}
@@ -0,0 +1,47 @@
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// The Fyers API allows at most 10 requests per second per app.
+pub const DEFAULT_REQUESTS_PER_SECOND: u32 = 10;
+
+/// A simple rate limiter that spaces out requests evenly. Clones of a client share the same
+/// limiter, so concurrent calls made through them are limited together.
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    next_slot: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// # Description
+    /// Create a new rate limiter
+    ///
+    /// # Arguments
+    /// * `requests_per_second` - The maximum number of requests to allow per second
+    pub fn new(requests_per_second: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(1) / requests_per_second.max(1),
+            next_slot: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// # Description
+    /// Wait until the next request is allowed to start
+    pub async fn acquire(&self) {
+        let slot = {
+            let mut next_slot = self.next_slot.lock().await;
+            let now = Instant::now();
+            let slot = if *next_slot > now { *next_slot } else { now };
+            *next_slot = slot + self.interval;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(DEFAULT_REQUESTS_PER_SECOND)
+    }
+}
@@ -0,0 +1,426 @@
+use crate::broker::Broker;
+use crate::dataapi::DataApi;
+use crate::error::FyersError;
+use crate::models::orders::ModifyOrderRequest;
+use crate::models::{FundsResponse, OrdersResponse, PositionsResponse, SingleOrderRequest, SingleOrderResponse, TradesResponse};
+use crate::kill_switch::KillSwitchHandle;
+use crate::paper::segment_of;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use thiserror::Error;
+
+/// The reason an order was rejected by a [RiskManager]
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum RiskViolation {
+    #[error("quantity {qty} is above the limit of {limit}")]
+    MaxQuantity { qty: i64, limit: i64 },
+
+    #[error("order value {notional:.2} is above the limit of {limit:.2}")]
+    MaxNotional { notional: f64, limit: f64 },
+
+    #[error("{open} positions are open, the limit is {limit}")]
+    MaxOpenPositions { open: usize, limit: usize },
+
+    #[error("exposure in {symbol} would be {exposure:.2}, the limit is {limit:.2}")]
+    MaxSymbolExposure { symbol: String, exposure: f64, limit: f64 },
+
+    #[error("today's P&L of {pl:.2} has reached the daily loss limit of {limit:.2}")]
+    DailyLossLimit { pl: f64, limit: f64 },
+
+    #[error("product type {0} is not allowed")]
+    ProductNotAllowed(String),
+
+    #[error("segment {0} is not allowed")]
+    SegmentNotAllowed(i64),
+
+    #[error("the segment of {0} is not known")]
+    UnknownSegment(String),
+
+    #[error("price {price:.2} is more than {band:.2}% away from the last price {ltp:.2}")]
+    PriceBand { price: f64, ltp: f64, band: f64 },
+
+    #[error("no price is known for {0}")]
+    PriceUnavailable(String),
+
+    #[error("the kill switch is engaged")]
+    KillSwitchEngaged,
+}
+
+/// # Description
+/// The limits checked by a [RiskManager]. A limit left as `None` (or empty) is not checked.
+#[derive(Debug, Clone, Default)]
+pub struct RiskLimits {
+    /// Largest quantity of a single order
+    pub max_order_qty: Option<i64>,
+    /// Largest value (quantity times price) of a single order
+    pub max_order_notional: Option<f64>,
+    /// Largest number of symbols with an open position. Orders that open a position in a new
+    /// symbol are rejected once it is reached.
+    pub max_open_positions: Option<usize>,
+    /// Largest exposure (absolute net quantity times price) in any one symbol
+    pub max_symbol_exposure: Option<f64>,
+    /// Exposure limits of individual symbols, taking precedence over `max_symbol_exposure`
+    pub symbol_exposure: HashMap<String, f64>,
+    /// Largest loss of the day, as a positive amount. Once `overall.pl_total` of the positions
+    /// reaches it, only orders that reduce a position are accepted.
+    pub max_daily_loss: Option<f64>,
+    /// Product types orders may use (e.g. "INTRADAY", "CNC")
+    pub allowed_products: Option<Vec<String>>,
+    /// Segments orders may trade in (10: capital market, 11: equity derivatives, 12: currency
+    /// derivatives, 20: commodity)
+    pub allowed_segments: Option<Vec<i64>>,
+    /// Largest distance of a limit or stop price from the last price, as a fraction of the
+    /// last price (e.g. 0.05 for 5%)
+    pub price_band: Option<f64>,
+}
+
+impl RiskLimits {
+    fn needs_price(&self) -> bool {
+        self.max_order_notional.is_some()
+            || self.max_symbol_exposure.is_some()
+            || !self.symbol_exposure.is_empty()
+            || self.price_band.is_some()
+    }
+}
+
+/// # Description
+/// A pre-trade risk layer in front of a [Broker]. Every order is checked against the
+/// [RiskLimits] before it is passed on, and orders that break a limit fail with
+/// [FyersError::RiskRejected] without reaching the broker.
+///
+/// Exits sent with [Broker::place_exit_order], e.g. by a
+/// [KillSwitch](crate::kill_switch::KillSwitch), are not checked.
+///
+/// Last prices come from [RiskManager::on_price] (e.g. fed from the market data websocket), and
+/// otherwise from a quote when a [DataApi] is configured.
+#[derive(Debug)]
+pub struct RiskManager<B> {
+    broker: B,
+    limits: RiskLimits,
+    data: Option<DataApi>,
+    kill_switch: Option<KillSwitchHandle>,
+    last_prices: Mutex<HashMap<String, f64>>,
+}
+
+impl<B: Broker> RiskManager<B> {
+    /// # Description
+    /// Put a risk layer in front of a broker
+    ///
+    /// # Arguments
+    /// * `broker` - The broker orders are passed on to
+    /// * `limits` - The limits to check
+    pub fn new(broker: B, limits: RiskLimits) -> Self {
+        Self {
+            broker,
+            limits,
+            data: None,
+            kill_switch: None,
+            last_prices: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// # Description
+    /// Fetch quotes for symbols without a known last price
+    pub fn with_data_api(mut self, data: DataApi) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    /// # Description
+    /// Engage a kill switch when the daily loss limit is reached
+    ///
+    /// # Arguments
+    /// * `handle` - The handle of the kill switch, from [KillSwitch::handle](crate::kill_switch::KillSwitch::handle)
+    pub fn with_kill_switch(mut self, handle: KillSwitchHandle) -> Self {
+        self.kill_switch = Some(handle);
+        self
+    }
+
+    pub fn broker(&self) -> &B {
+        &self.broker
+    }
+
+    pub fn limits(&self) -> &RiskLimits {
+        &self.limits
+    }
+
+    /// # Description
+    /// Replace the limits, e.g. after a configuration reload
+    pub fn set_limits(&mut self, limits: RiskLimits) {
+        self.limits = limits;
+    }
+
+    /// # Description
+    /// Record the last traded price of a symbol
+    pub fn on_price(&self, symbol: &str, price: f64) {
+        self.last_prices
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(symbol.to_string(), price);
+    }
+
+    async fn last_price(&self, symbol: &str) -> Result<f64, FyersError> {
+        let known = self
+            .last_prices
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(symbol)
+            .copied();
+        if let Some(price) = known {
+            return Ok(price);
+        }
+
+        if let Some(data) = &self.data {
+            if let Some(Ok(quote)) = data.get_market_quotes_batched(&[symbol]).await.remove(symbol) {
+                self.on_price(symbol, quote.lp);
+                return Ok(quote.lp);
+            }
+        }
+        Err(FyersError::RiskRejected(RiskViolation::PriceUnavailable(symbol.to_string())))
+    }
+
+    /// # Description
+    /// Check an order against the limits without placing it
+    ///
+    /// # Arguments
+    /// * `order` - The order to check
+    pub async fn check(&self, order: &SingleOrderRequest) -> Result<(), FyersError> {
+        let limits = &self.limits;
+
+        if let Some(limit) = limits.max_order_qty {
+            if order.qty > limit {
+                return Err(FyersError::RiskRejected(RiskViolation::MaxQuantity { qty: order.qty, limit }));
+            }
+        }
+        if let Some(allowed) = &limits.allowed_products {
+            if !allowed.iter().any(|product| product.eq_ignore_ascii_case(&order.product_type)) {
+                return Err(FyersError::RiskRejected(RiskViolation::ProductNotAllowed(order.product_type.clone())));
+            }
+        }
+        if let Some(allowed) = &limits.allowed_segments {
+            let Some((_, segment)) = segment_of(&order.symbol) else {
+                return Err(FyersError::RiskRejected(RiskViolation::UnknownSegment(order.symbol.clone())));
+            };
+            if !allowed.contains(&segment) {
+                return Err(FyersError::RiskRejected(RiskViolation::SegmentNotAllowed(segment)));
+            }
+        }
+
+        let ltp = if limits.needs_price() {
+            Some(self.last_price(&order.symbol).await?)
+        } else {
+            None
+        };
+        // The price the order is expected to trade at
+        let order_price = match order.order_type {
+            1 | 4 => order.limit_price,
+            3 => order.stop_price,
+            _ => ltp.unwrap_or(0.0),
+        };
+
+        if let (Some(band), Some(ltp)) = (limits.price_band, ltp) {
+            for price in [order.limit_price, order.stop_price] {
+                if price > 0.0 && ltp > 0.0 && ((price - ltp) / ltp).abs() > band {
+                    return Err(FyersError::RiskRejected(RiskViolation::PriceBand { price, ltp, band: band * 100.0 }));
+                }
+            }
+        }
+        if let Some(limit) = limits.max_order_notional {
+            let notional = order_price * order.qty as f64;
+            if notional > limit {
+                return Err(FyersError::RiskRejected(RiskViolation::MaxNotional { notional, limit }));
+            }
+        }
+
+        let position_limits = limits.max_open_positions.is_some()
+            || limits.max_daily_loss.is_some()
+            || limits.max_symbol_exposure.is_some()
+            || !limits.symbol_exposure.is_empty();
+        if !position_limits {
+            return Ok(());
+        }
+
+        let positions = self.broker.positions().await?;
+        let net_qty: i64 = positions
+            .net_positions
+            .iter()
+            .filter(|position| position.symbol == order.symbol)
+            .map(|position| position.net_qty)
+            .sum();
+        let new_net_qty = net_qty + order.side * order.qty;
+        let increases_exposure = new_net_qty.abs() > net_qty.abs();
+
+        if let Some(limit) = limits.max_daily_loss {
+            if increases_exposure && positions.overall.pl_total <= -limit {
+                if let Some(kill_switch) = &self.kill_switch {
+                    kill_switch.engage();
+                }
+                return Err(FyersError::RiskRejected(RiskViolation::DailyLossLimit { pl: positions.overall.pl_total, limit }));
+            }
+        }
+        if let Some(limit) = limits.max_open_positions {
+            let open: Vec<&str> = positions
+                .net_positions
+                .iter()
+                .filter(|position| position.net_qty != 0)
+                .map(|position| position.symbol.as_str())
+                .collect();
+            if net_qty == 0 && !open.contains(&order.symbol.as_str()) && open.len() >= limit {
+                return Err(FyersError::RiskRejected(RiskViolation::MaxOpenPositions { open: open.len(), limit }));
+            }
+        }
+        let exposure_limit = limits
+            .symbol_exposure
+            .get(&order.symbol)
+            .copied()
+            .or(limits.max_symbol_exposure);
+        if let Some(limit) = exposure_limit {
+            let exposure = new_net_qty.abs() as f64 * order_price;
+            if increases_exposure && exposure > limit {
+                return Err(FyersError::RiskRejected(RiskViolation::MaxSymbolExposure {
+                    symbol: order.symbol.clone(),
+                    exposure,
+                    limit,
+                }));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<B: Broker> Broker for RiskManager<B> {
+    async fn place_order(&self, order: &SingleOrderRequest) -> Result<SingleOrderResponse, FyersError> {
+        self.check(order).await?;
+        self.broker.place_order(order).await
+    }
+
+    // Exits reduce risk, and must not be stopped by the limits meant for new orders
+    async fn place_exit_order(&self, order: &SingleOrderRequest) -> Result<SingleOrderResponse, FyersError> {
+        self.broker.place_exit_order(order).await
+    }
+
+    // A modification is checked as the order it would leave pending: the symbol, side and product
+    // of the original order with the modified quantity, prices and type. The quantity limit
+    // applies to the whole order, the other limits to its unfilled quantity.
+    async fn modify_order(&self, request: &ModifyOrderRequest) -> Result<SingleOrderResponse, FyersError> {
+        let orders = self.broker.orders().await?;
+        let Some(original) = orders.order_book.iter().find(|order| order.id == request.id) else {
+            return Err(FyersError::InvalidRequest(format!("order {} was not found", request.id)));
+        };
+
+        let qty = request.qty.unwrap_or(original.qty);
+        if let Some(limit) = self.limits.max_order_qty {
+            if qty > limit {
+                return Err(FyersError::RiskRejected(RiskViolation::MaxQuantity { qty, limit }));
+            }
+        }
+        let pending = SingleOrderRequest {
+            symbol: original.symbol.clone(),
+            qty: (qty - original.filled_qty).max(0),
+            order_type: request.order_type.unwrap_or(original.in_type),
+            side: original.side,
+            product_type: original.product_type.clone(),
+            validity: original.order_validity.clone(),
+            offline_order: original.offline_order,
+            limit_price: request.limit_price.unwrap_or(original.limit_price),
+            stop_price: request.stop_price.unwrap_or(original.stop_price),
+            disclosed_qty: original.disclosed_qty,
+            stop_loss: 0.0,
+            take_profit: 0.0,
+            order_tag: None,
+        };
+        self.check(&pending).await?;
+        self.broker.modify_order(request).await
+    }
+
+    async fn cancel_order(&self, id: &str) -> Result<SingleOrderResponse, FyersError> {
+        self.broker.cancel_order(id).await
+    }
+
+    async fn orders(&self) -> Result<OrdersResponse, FyersError> {
+        self.broker.orders().await
+    }
+
+    async fn positions(&self) -> Result<PositionsResponse, FyersError> {
+        self.broker.positions().await
+    }
+
+    async fn trades(&self) -> Result<TradesResponse, FyersError> {
+        self.broker.trades().await
+    }
+
+    async fn funds(&self) -> Result<FundsResponse, FyersError> {
+        self.broker.funds().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paper::PaperBroker;
+
+    fn limit_buy(symbol: &str, qty: i64, limit_price: f64) -> SingleOrderRequest {
+        SingleOrderRequest {
+            symbol: symbol.to_string(),
+            qty,
+            order_type: 1,
+            side: 1,
+            product_type: "INTRADAY".to_string(),
+            validity: "DAY".to_string(),
+            offline_order: false,
+            limit_price,
+            stop_price: 0.0,
+            disclosed_qty: 0,
+            stop_loss: 0.0,
+            take_profit: 0.0,
+            order_tag: None,
+        }
+    }
+
+    fn violation(result: Result<SingleOrderResponse, FyersError>) -> RiskViolation {
+        match result {
+            Err(FyersError::RiskRejected(violation)) => violation,
+            other => panic!("expected a risk rejection, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn modifications_are_checked_against_the_original_order() {
+        let limits = RiskLimits {
+            max_order_notional: Some(10_000.0),
+            price_band: Some(0.05),
+            ..Default::default()
+        };
+        let risk = RiskManager::new(PaperBroker::new(100_000.0), limits);
+        risk.on_price("NSE:SBIN-EQ", 800.0);
+        let id = risk.place_order(&limit_buy("NSE:SBIN-EQ", 10, 790.0)).await.unwrap().id;
+
+        let mut request = ModifyOrderRequest::new(&id);
+        request.limit_price = Some(900.0);
+        assert!(matches!(violation(risk.modify_order(&request).await), RiskViolation::PriceBand { .. }));
+
+        let mut request = ModifyOrderRequest::new(&id);
+        request.qty = Some(20);
+        assert!(matches!(violation(risk.modify_order(&request).await), RiskViolation::MaxNotional { .. }));
+
+        let mut request = ModifyOrderRequest::new(&id);
+        request.limit_price = Some(795.0);
+        assert!(risk.modify_order(&request).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn segment_limits_classify_currency_and_fail_closed() {
+        let limits = RiskLimits {
+            allowed_segments: Some(vec![10, 11]),
+            ..Default::default()
+        };
+        let risk = RiskManager::new(PaperBroker::new(100_000.0), limits);
+
+        let currency = risk.place_order(&limit_buy("NSE:USDINR25SEPFUT", 1, 83.5)).await;
+        assert_eq!(violation(currency), RiskViolation::SegmentNotAllowed(12));
+        let unknown = risk.place_order(&limit_buy("SBIN-EQ", 1, 800.0)).await;
+        assert_eq!(violation(unknown), RiskViolation::UnknownSegment("SBIN-EQ".to_string()));
+        assert!(risk.place_order(&limit_buy("NSE:SBIN-EQ", 1, 800.0)).await.is_ok());
+    }
+}
@@ -0,0 +1,418 @@
+use crate::charges::{ChargeTable, Segment};
+use crate::models::transaction::Trade;
+use crate::models::PositionsResponse;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::Duration;
+
+// IST offset from UTC, in seconds
+const IST_OFFSET: i64 = 19_800;
+
+/// A closed round trip: an entry matched with an exit of the same symbol and product type
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundTrip {
+    pub symbol: String,
+    pub product_type: String,
+    /// 1 for a long round trip, -1 for a short one
+    pub side: i64,
+    pub qty: i64,
+    /// Time of the entry trade, `None` when the entry was carried forward from an earlier day
+    pub entry_time: Option<i64>,
+    pub exit_time: Option<i64>,
+    pub entry_price: f64,
+    pub exit_price: f64,
+    /// Whether the entry was carried forward from an earlier day
+    pub carried_forward: bool,
+    pub gross_pnl: f64,
+    /// Charges of the entry and exit, pro rata to the quantity of the round trip. Charges of a
+    /// carried forward entry were paid on an earlier day and are not included.
+    pub charges: f64,
+    pub net_pnl: f64,
+}
+
+impl RoundTrip {
+    /// Time between the entry and the exit, when both are known
+    pub fn holding_period(&self) -> Option<Duration> {
+        match (self.entry_time, self.exit_time) {
+            (Some(entry), Some(exit)) if exit >= entry => Some(Duration::from_secs((exit - entry) as u64)),
+            _ => None,
+        }
+    }
+}
+
+/// Quantity that is still open after matching
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenLot {
+    pub symbol: String,
+    pub product_type: String,
+    /// 1 for long, -1 for short
+    pub side: i64,
+    pub qty: i64,
+    pub price: f64,
+    pub entry_time: Option<i64>,
+    pub carried_forward: bool,
+}
+
+/// The result of matching a trade book
+#[derive(Debug, Clone, Default)]
+pub struct RoundTripReport {
+    pub round_trips: Vec<RoundTrip>,
+    pub open_lots: Vec<OpenLot>,
+}
+
+impl RoundTripReport {
+    pub fn gross_pnl(&self) -> f64 {
+        self.round_trips.iter().map(|trip| trip.gross_pnl).sum()
+    }
+
+    pub fn charges(&self) -> f64 {
+        self.round_trips.iter().map(|trip| trip.charges).sum()
+    }
+
+    pub fn net_pnl(&self) -> f64 {
+        self.round_trips.iter().map(|trip| trip.net_pnl).sum()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Lot {
+    side: i64,
+    qty: i64,
+    price: f64,
+    time: Option<i64>,
+    charges_per_unit: UnitCharges,
+    carried_forward: bool,
+}
+
+/// Charges per unit of an order, as traded and as if it were an intraday order
+#[derive(Debug, Clone, Copy, Default)]
+struct UnitCharges {
+    traded: f64,
+    intraday: f64,
+}
+
+impl UnitCharges {
+    fn get(&self, intraday: bool) -> f64 {
+        if intraday {
+            self.intraday
+        } else {
+            self.traded
+        }
+    }
+}
+
+/// # Description
+/// Match the trades of a trade book FIFO per symbol and product type into round trips. Partial
+/// fills are matched quantity by quantity.
+///
+/// Positions carried forward from earlier days are not in the trade book. Pass the positions to
+/// open them as the first lots of their symbol, from `cf_buy_qty` and `cf_sell_qty`.
+///
+/// A delivery (CNC or MTF) round trip entered and exited on the same trading day is settled as
+/// intraday, so it is charged at the intraday rates.
+///
+/// # Arguments
+/// * `trades` - The trades, e.g. `trade_book` of [Transaction::get_trades](crate::transaction::Transaction::get_trades)
+/// * `positions` - The positions, to account for carried forward quantity
+/// * `charges` - The rates of every segment to compute the charges of every order with
+pub fn match_round_trips(trades: &[Trade], positions: Option<&PositionsResponse>, charges: &ChargeTable) -> RoundTripReport {
+    let charges_per_unit = charges_per_unit(trades, charges);
+
+    let mut sorted: Vec<&Trade> = trades.iter().collect();
+    sorted.sort_by_key(|trade| (parse_order_time(&trade.order_date_time).unwrap_or(i64::MIN), trade.row));
+
+    let mut books: BTreeMap<(String, String), VecDeque<Lot>> = BTreeMap::new();
+    if let Some(positions) = positions {
+        for position in &positions.net_positions {
+            let book = books
+                .entry((position.symbol.clone(), position.product_type.clone()))
+                .or_default();
+            let day_value = |side: i64| -> f64 {
+                trades
+                    .iter()
+                    .filter(|t| t.symbol == position.symbol && t.product_type == position.product_type && t.side == side)
+                    .map(|t| t.trade_price * t.traded_qty as f64)
+                    .sum()
+            };
+            let carried = [
+                (1, position.cf_buy_qty, position.buy_qty, position.buy_avg),
+                (-1, position.cf_sell_qty, position.sell_qty, position.sell_avg),
+            ];
+            for (side, cf_qty, total_qty, average) in carried {
+                if cf_qty <= 0 {
+                    continue;
+                }
+                // The average covers the carried and the day's quantity, take the day's value out
+                let price = (average * total_qty as f64 - day_value(side)) / cf_qty as f64;
+                book.push_back(Lot {
+                    side,
+                    qty: cf_qty,
+                    price: if price > 0.0 { price } else { average },
+                    time: None,
+                    charges_per_unit: UnitCharges::default(),
+                    carried_forward: true,
+                });
+            }
+        }
+    }
+
+    let mut report = RoundTripReport::default();
+    for trade in sorted {
+        let key = (trade.symbol.clone(), trade.product_type.clone());
+        let book = books.entry(key).or_default();
+        let time = parse_order_time(&trade.order_date_time);
+        let exit_charges = charges_per_unit.get(&trade.order_number).copied().unwrap_or_default();
+        let mut remaining = trade.traded_qty;
+
+        while remaining > 0 {
+            let Some(lot) = book.front_mut().filter(|lot| lot.side == -trade.side) else { break };
+            let matched = remaining.min(lot.qty);
+            let gross_pnl = (trade.trade_price - lot.price) * matched as f64 * lot.side as f64;
+            let intraday = same_trading_day(lot.time, time);
+            let charges = (lot.charges_per_unit.get(intraday) + exit_charges.get(intraday)) * matched as f64;
+            report.round_trips.push(RoundTrip {
+                symbol: trade.symbol.clone(),
+                product_type: trade.product_type.clone(),
+                side: lot.side,
+                qty: matched,
+                entry_time: lot.time,
+                exit_time: time,
+                entry_price: lot.price,
+                exit_price: trade.trade_price,
+                carried_forward: lot.carried_forward,
+                gross_pnl,
+                charges,
+                net_pnl: gross_pnl - charges,
+            });
+
+            lot.qty -= matched;
+            if lot.qty == 0 {
+                book.pop_front();
+            }
+            remaining -= matched;
+        }
+
+        if remaining > 0 {
+            book.push_back(Lot {
+                side: trade.side,
+                qty: remaining,
+                price: trade.trade_price,
+                time,
+                charges_per_unit: exit_charges,
+                carried_forward: false,
+            });
+        }
+    }
+
+    for ((symbol, product_type), book) in books {
+        report.open_lots.extend(book.into_iter().map(|lot| OpenLot {
+            symbol: symbol.clone(),
+            product_type: product_type.clone(),
+            side: lot.side,
+            qty: lot.qty,
+            price: lot.price,
+            entry_time: lot.time,
+            carried_forward: lot.carried_forward,
+        }));
+    }
+    report
+}
+
+/// Charges per unit of every order. Brokerage is capped per order, so the charges are computed
+/// on the whole order and spread over its fills.
+fn charges_per_unit(trades: &[Trade], charges: &ChargeTable) -> HashMap<String, UnitCharges> {
+    let mut orders: HashMap<&str, (&Trade, i64, f64)> = HashMap::new();
+    for trade in trades {
+        let order = orders.entry(&trade.order_number).or_insert((trade, 0, 0.0));
+        order.1 += trade.traded_qty;
+        order.2 += trade.trade_price * trade.traded_qty as f64;
+    }
+    orders
+        .into_iter()
+        .filter(|(_, (_, qty, _))| *qty > 0)
+        .map(|(id, (trade, qty, value))| {
+            let per_unit = |segment| charges.compute(segment, trade.side, value).total() / qty as f64;
+            let intraday = Segment::classify(trade.segment, &trade.symbol, "INTRADAY");
+            let unit = UnitCharges {
+                traded: per_unit(Segment::of_trade(trade)),
+                intraday: per_unit(intraday),
+            };
+            (id.to_string(), unit)
+        })
+        .collect()
+}
+
+/// Whether two epoch times fall on the same IST trading day
+fn same_trading_day(entry: Option<i64>, exit: Option<i64>) -> bool {
+    match (entry, exit) {
+        (Some(entry), Some(exit)) => {
+            (entry + IST_OFFSET).div_euclid(86_400) == (exit + IST_OFFSET).div_euclid(86_400)
+        }
+        _ => false,
+    }
+}
+
+/// # Description
+/// Parse an order book time such as "05-Sep-2025 10:15:23" (IST) into epoch seconds
+pub(crate) fn parse_order_time(time: &str) -> Option<i64> {
+    const MONTHS: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+    let (date, clock) = time.trim().split_once(' ')?;
+    let mut date = date.split('-');
+    let day: i64 = date.next()?.parse().ok()?;
+    let month = date.next()?.to_ascii_lowercase();
+    let month = MONTHS.iter().position(|m| *m == month)? as i64 + 1;
+    let year: i64 = date.next()?.parse().ok()?;
+    let mut clock = clock.trim().split(':');
+    let hour: i64 = clock.next()?.parse().ok()?;
+    let minute: i64 = clock.next()?.parse().ok()?;
+    let second: i64 = clock.next().map_or(Some(0), |s| s.parse().ok())?;
+
+    // Days since the epoch from a civil date (Howard Hinnant's algorithm)
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    Some(days * 86_400 + hour * 3_600 + minute * 60 + second - IST_OFFSET)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::transaction::{NetPosition, Overall};
+
+    fn trade(order_number: &str, time: &str, side: i64, qty: i64, price: f64) -> Trade {
+        Trade {
+            symbol: "NSE:SBIN-EQ".to_string(),
+            row: 0,
+            order_date_time: time.to_string(),
+            order_number: order_number.to_string(),
+            trade_number: format!("{}-{}", order_number, time),
+            trade_price: price,
+            trade_value: price * qty as f64,
+            traded_qty: qty,
+            side,
+            product_type: "CNC".to_string(),
+            exchange_order_no: String::new(),
+            segment: 10,
+            exchange: 10,
+            fy_token: String::new(),
+            order_tag: String::new(),
+        }
+    }
+
+    fn positions(cf_buy_qty: i64, buy_qty: i64, buy_avg: f64) -> PositionsResponse {
+        PositionsResponse {
+            s: "ok".to_string(),
+            code: 200,
+            message: String::new(),
+            net_positions: vec![NetPosition {
+                symbol: "NSE:SBIN-EQ".to_string(),
+                id: String::new(),
+                buy_avg,
+                buy_qty,
+                sell_avg: 0.0,
+                sell_qty: 0,
+                net_avg: buy_avg,
+                net_qty: buy_qty,
+                side: 1,
+                qty: buy_qty,
+                product_type: "CNC".to_string(),
+                realized_profit: 0.0,
+                pl: 0.0,
+                cross_currency: String::new(),
+                rbi_ref_rate: 1.0,
+                qty_multi_com: 1.0,
+                segment: 10,
+                exchange: 10,
+                sl_no: 0,
+                ltp: 0.0,
+                fy_token: String::new(),
+                cf_buy_qty,
+                cf_sell_qty: 0,
+                day_buy_qty: buy_qty - cf_buy_qty,
+                day_sell_qty: 0,
+            }],
+            overall: Overall {
+                count_total: 1,
+                count_open: 1,
+                pl_total: 0.0,
+                pl_realized: 0.0,
+                pl_unrealized: 0.0,
+            },
+        }
+    }
+
+    #[test]
+    fn parses_order_book_times_as_ist() {
+        assert_eq!(parse_order_time("05-Sep-2025 09:15:00"), Some(1_757_043_900));
+        assert_eq!(parse_order_time("05-SEP-2025 09:15"), Some(1_757_043_900));
+        assert_eq!(parse_order_time("01-Mar-2024 00:00:00"), Some(1_709_231_400));
+        assert_eq!(parse_order_time("2025-09-05 09:15:00"), None);
+        assert_eq!(parse_order_time(""), None);
+    }
+
+    #[test]
+    fn matches_partial_fills_fifo() {
+        let trades = [
+            trade("3", "05-Sep-2025 11:00:00", -1, 15, 105.0),
+            trade("1", "05-Sep-2025 09:30:00", 1, 10, 100.0),
+            trade("2", "05-Sep-2025 10:00:00", 1, 5, 102.0),
+            trade("2", "05-Sep-2025 10:00:01", 1, 5, 102.0),
+        ];
+        let report = match_round_trips(&trades, None, &ChargeTable::zero());
+
+        let trips: Vec<(i64, f64, f64)> = report.round_trips.iter().map(|t| (t.qty, t.entry_price, t.gross_pnl)).collect();
+        assert_eq!(trips, vec![(10, 100.0, 50.0), (5, 102.0, 15.0)]);
+        assert_eq!(report.round_trips[0].holding_period(), Some(Duration::from_secs(5_400)));
+        assert_eq!(report.open_lots.len(), 1);
+        assert_eq!((report.open_lots[0].qty, report.open_lots[0].price), (5, 102.0));
+        assert_eq!(report.net_pnl(), 65.0);
+    }
+
+    #[test]
+    fn carried_forward_quantity_is_matched_first() {
+        // 10 carried at 100 and 5 bought today at 106 average 102
+        let trades = [
+            trade("1", "05-Sep-2025 09:20:00", 1, 5, 106.0),
+            trade("2", "05-Sep-2025 10:00:00", -1, 12, 110.0),
+        ];
+        let report = match_round_trips(&trades, Some(&positions(10, 15, 102.0)), &ChargeTable::default());
+
+        let carried = &report.round_trips[0];
+        assert!(carried.carried_forward);
+        assert_eq!((carried.qty, carried.entry_time, carried.holding_period()), (10, None, None));
+        assert!((carried.entry_price - 100.0).abs() < 1e-9);
+        // Only the exit's share of the charges, the entry's were paid on an earlier day
+        let exit_per_unit = ChargeTable::default().for_trade(&trades[1]).total() / 12.0;
+        assert!((carried.charges - exit_per_unit * 10.0).abs() < 1e-9);
+
+        let day = &report.round_trips[1];
+        assert_eq!((day.qty, day.entry_price, day.carried_forward), (2, 106.0, false));
+        assert_eq!((report.open_lots[0].qty, report.open_lots[0].price), (3, 106.0));
+    }
+
+    #[test]
+    fn same_day_delivery_round_trips_are_charged_as_intraday() {
+        let table = ChargeTable::default();
+        let per_unit = |trade: &Trade, segment| table.compute(segment, trade.side, trade.trade_value).total() / trade.traded_qty as f64;
+        let trades = [
+            trade("1", "04-Sep-2025 15:00:00", 1, 10, 100.0),
+            trade("2", "05-Sep-2025 09:30:00", 1, 10, 102.0),
+            trade("3", "05-Sep-2025 14:00:00", -1, 20, 105.0),
+        ];
+        let report = match_round_trips(&trades, None, &table);
+        assert_eq!(report.round_trips.len(), 2);
+
+        let overnight = &report.round_trips[0];
+        let delivery = per_unit(&trades[0], Segment::EquityDelivery) + per_unit(&trades[2], Segment::EquityDelivery);
+        assert!((overnight.charges - delivery * 10.0).abs() < 1e-9);
+
+        let same_day = &report.round_trips[1];
+        let intraday = per_unit(&trades[1], Segment::EquityIntraday) + per_unit(&trades[2], Segment::EquityIntraday);
+        assert!((same_day.charges - intraday * 10.0).abs() < 1e-9);
+        assert!(same_day.charges < overnight.charges);
+    }
+}
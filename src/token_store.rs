@@ -0,0 +1,226 @@
+use crate::error::FyersError;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Claims we read from the payload of a FYERS access token
+#[derive(Deserialize, Debug)]
+struct TokenClaims {
+    #[serde(default)]
+    iat: Option<i64>,
+    exp: i64,
+    #[serde(default)]
+    fy_id: Option<String>,
+}
+
+/// An access token along with the times it was issued and expires at
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredToken {
+    pub access_token: String,
+    /// Issue time, in epoch seconds
+    pub issued_at: i64,
+    /// Expiry time, in epoch seconds
+    pub expires_at: i64,
+    /// The FYERS client id the token was issued for, if present in the token
+    #[serde(default)]
+    pub fy_id: Option<String>,
+    /// The refresh token issued with the access token, used to get a new access token without
+    /// logging in again
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+}
+
+impl StoredToken {
+    /// # Description
+    /// Build a stored token from an access token, reading the issue and expiry times from its JWT
+    /// claims.
+    ///
+    /// # Arguments
+    /// * `access_token` - The access token returned by [generate_access_token](crate::auth::generate_access_token)
+    pub fn from_access_token(access_token: &str) -> Result<Self, FyersError> {
+        let claims = decode_claims(access_token)?;
+        Ok(Self {
+            access_token: access_token.to_string(),
+            issued_at: claims.iat.unwrap_or_else(unix_now),
+            expires_at: claims.exp,
+            fy_id: claims.fy_id,
+            refresh_token: None,
+        })
+    }
+
+    /// Whether the token has expired
+    pub fn is_expired(&self) -> bool {
+        self.expires_within(Duration::ZERO)
+    }
+
+    /// # Description
+    /// Whether the token has expired or will expire within `margin`
+    ///
+    /// # Arguments
+    /// * `margin` - How far ahead to look, e.g. `Duration::from_secs(15 * 60)`
+    pub fn expires_within(&self, margin: Duration) -> bool {
+        unix_now() + margin.as_secs() as i64 >= self.expires_at
+    }
+
+    /// The time left before the token expires, or zero if it has expired
+    pub fn time_to_expiry(&self) -> Duration {
+        Duration::from_secs((self.expires_at - unix_now()).max(0) as u64)
+    }
+}
+
+/// Persistent storage for an access token. Implementations must be safe to share between clients.
+pub trait TokenStore: Send + Sync + Debug {
+    /// Load the stored token, if there is one
+    fn load(&self) -> Result<Option<StoredToken>, FyersError>;
+    /// Save a token, replacing any stored token
+    fn save(&self, token: &StoredToken) -> Result<(), FyersError>;
+    /// Remove the stored token
+    fn clear(&self) -> Result<(), FyersError>;
+}
+
+/// A token store that keeps the token in memory only
+#[derive(Debug, Default)]
+pub struct MemoryTokenStore {
+    token: Mutex<Option<StoredToken>>,
+}
+
+impl MemoryTokenStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TokenStore for MemoryTokenStore {
+    fn load(&self) -> Result<Option<StoredToken>, FyersError> {
+        Ok(self.token.lock().unwrap_or_else(|e| e.into_inner()).clone())
+    }
+
+    fn save(&self, token: &StoredToken) -> Result<(), FyersError> {
+        *self.token.lock().unwrap_or_else(|e| e.into_inner()) = Some(token.clone());
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<(), FyersError> {
+        *self.token.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        Ok(())
+    }
+}
+
+/// A token store that keeps the token in a JSON file
+#[derive(Debug, Clone)]
+pub struct FileTokenStore {
+    path: PathBuf,
+}
+
+impl FileTokenStore {
+    /// # Description
+    /// Create a new file token store. The file is created on the first save.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the JSON file to store the token in
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl TokenStore for FileTokenStore {
+    fn load(&self) -> Result<Option<StoredToken>, FyersError> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(content) => Ok(Some(serde_json::from_str(&content)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&self, token: &StoredToken) -> Result<(), FyersError> {
+        // Write to a temporary file first so that a crash never leaves a half written token. The
+        // file is created readable by the owner only, so the token is never exposed.
+        let tmp_path = self.path.with_extension("tmp");
+        match std::fs::remove_file(&tmp_path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&tmp_path)?;
+        file.write_all(serde_json::to_string_pretty(token)?.as_bytes())?;
+        file.sync_all()?;
+        drop(file);
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<(), FyersError> {
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Decode the claims from the payload of a JWT, without verifying its signature
+fn decode_claims(token: &str) -> Result<TokenClaims, FyersError> {
+    let payload = token
+        .split('.')
+        .nth(1)
+        .ok_or_else(|| FyersError::AuthError("Access token is not a JWT".to_string()))?;
+    let bytes = URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .map_err(|e| FyersError::AuthError(format!("Could not decode access token: {}", e)))?;
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+/// The current time in epoch seconds
+pub(crate) fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_store_round_trips_with_owner_only_permissions() {
+        let path = std::env::temp_dir().join(format!("fyers_token_store_{}.json", std::process::id()));
+        let store = FileTokenStore::new(&path);
+        let token = StoredToken {
+            access_token: "header.payload.signature".to_string(),
+            issued_at: 1,
+            expires_at: 2,
+            fy_id: Some("XY00000".to_string()),
+            refresh_token: None,
+        };
+
+        store.save(&token).unwrap();
+        store.save(&token).unwrap();
+        assert_eq!(store.load().unwrap(), Some(token));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        store.clear().unwrap();
+        assert_eq!(store.load().unwrap(), None);
+    }
+}
@@ -1,15 +1,22 @@
+use crate::credentials::Credentials;
 use crate::error::FyersError;
+use crate::token_store::TokenStore;
 use crate::models::{OrdersResponse, PositionsResponse, TradesResponse};
+use crate::models::transaction::Order;
 use reqwest::Client;
+use std::sync::Arc;
+use std::time::Duration;
 
 const FYERS_API_BASE_URL: &str = "https://api-t1.fyers.in/api/v3";
 
+/// How often [Transaction::wait_for_terminal] reads the order book by default
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
 /// The Transaction Class. Implements the [Transaction Info](https://myapi.fyers.in/docsv3#tag/Transaction-Info) section of the official Fyers API
 #[derive(Debug, Clone)]
 pub struct Transaction {
     http_client: Client,
-    app_id: String,
-    access_token: String,
+    credentials: Credentials,
 }
 
 impl Transaction {
@@ -22,8 +29,29 @@ impl Transaction {
     pub fn new(app_id: String, access_token: String) -> Self {
         Self {
             http_client: Client::new(),
-            app_id,
-            access_token
+            credentials: Credentials::new(app_id, access_token),
+        }
+    }
+
+    /// # Description
+    /// Create a new instance of the Transaction class that reads its access token from a token store.
+    /// Calls fail with an auth error once the stored token has expired.
+    ///
+    /// # Arguments
+    /// * `app_id` - The app id of the user.
+    /// * `token_store` - The token store holding the access token of the user.
+    pub fn from_token_store(app_id: String, token_store: Arc<dyn TokenStore>) -> Result<Self, FyersError> {
+        Ok(Self {
+            http_client: Client::new(),
+            credentials: Credentials::from_token_store(app_id, token_store)?,
+        })
+    }
+
+    /// Create a client that shares its credentials, and so its logout, with other clients
+    pub(crate) fn with_credentials(credentials: Credentials) -> Self {
+        Self {
+            http_client: Client::new(),
+            credentials,
         }
     }
 
@@ -48,7 +76,7 @@ impl Transaction {
             url = format!("{}?{}", url, query_params.join("&"));
         }
 
-        let auth_header_value = format!("{}:{}", self.app_id, self.access_token);
+        let auth_header_value = self.credentials.auth_header()?;
 
         let response = self
             .http_client
@@ -81,7 +109,7 @@ impl Transaction {
     /// previous day's closed positions will not be shown here. [Read more](https://myapi.fyers.in/docsv3#tag/Transaction-Info)
     pub async fn get_positions(&self) -> Result<PositionsResponse, FyersError> {
         let url = format!("{}/positions", FYERS_API_BASE_URL);
-        let auth_header_value = format!("{}:{}", self.app_id, self.access_token);
+        let auth_header_value = self.credentials.auth_header()?;
         let response = self
             .http_client
             .get(&url)
@@ -123,7 +151,7 @@ impl Transaction {
         if !query_params.is_empty() {
             url = format!("{}?{}", url, query_params.join("&"));
         }
-        let auth_header_value = format!("{}:{}", self.app_id, self.access_token);
+        let auth_header_value = self.credentials.auth_header()?;
         let response = self
             .http_client
             .get(&url)
@@ -148,6 +176,98 @@ impl Transaction {
         }
     }
 
+    /// # Description
+    /// Wait for an order to reach a terminal state: traded (2), cancelled (1), rejected (5) or
+    /// expired (7). Resolves with the final order book entry, which carries the average traded
+    /// price and the filled quantity, or with a timeout error.
+    ///
+    /// # Arguments
+    /// * `id` - The order id, e.g. returned by [Order::single_order](crate::orders::Order::single_order)
+    /// * `timeout` - How long to wait
+    pub async fn wait_for_terminal(&self, id: &str, timeout: Duration) -> Result<Order, FyersError> {
+        self.wait_for_terminal_with(id, timeout, DEFAULT_POLL_INTERVAL, |_| {}).await
+    }
+
+    /// # Description
+    /// Same as [Transaction::wait_for_terminal], calling `on_partial_fill` every time the filled
+    /// quantity grows while the order is still open. Failed reads of the order book (e.g. network
+    /// errors or rate limits) are logged and retried; if the last read before the timeout failed,
+    /// its error is returned instead of the timeout. Auth errors are returned at once.
+    ///
+    /// # Arguments
+    /// * `id` - The order id
+    /// * `timeout` - How long to wait
+    /// * `poll_interval` - Time between reads of the order book
+    /// * `on_partial_fill` - Called with the order book entry after each partial fill
+    pub async fn wait_for_terminal_with<F>(
+        &self,
+        id: &str,
+        timeout: Duration,
+        poll_interval: Duration,
+        mut on_partial_fill: F,
+    ) -> Result<Order, FyersError>
+    where
+        F: FnMut(&Order),
+    {
+        let mut last_error = None;
+        let wait = async {
+            let mut filled_qty = 0;
+            loop {
+                match self.get_orders(Some(id), None).await {
+                    Ok(orders) => {
+                        last_error = None;
+                        if let Some(order) = orders.order_book.into_iter().find(|order| order.id == id) {
+                            if is_terminal_status(order.status) {
+                                return Ok(order);
+                            }
+                            if order.filled_qty > filled_qty {
+                                filled_qty = order.filled_qty;
+                                on_partial_fill(&order);
+                            }
+                        }
+                    }
+                    Err(e @ FyersError::AuthError(_)) => return Err(e),
+                    Err(e) => {
+                        log::warn!("Failed to read order {}, retrying: {}", id, e);
+                        last_error = Some(e);
+                    }
+                }
+                tokio::time::sleep(poll_interval).await;
+            }
+        };
 
+        match tokio::time::timeout(timeout, wait).await {
+            Ok(result) => result,
+            Err(_) => Err(last_error.unwrap_or_else(|| {
+                FyersError::Timeout(format!("Order {} did not reach a terminal state in {:?}", id, timeout))
+            })),
+        }
+    }
 }
 
+/// Whether an order book status is final: traded (2), cancelled (1), rejected (5) or expired (7)
+fn is_terminal_status(status: i64) -> bool {
+    matches!(status, 1 | 2 | 5 | 7)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::transaction::test_order;
+    use crate::order_tracker::OrderState;
+
+    #[test]
+    fn terminal_statuses() {
+        for status in [1, 2, 5, 7] {
+            assert!(is_terminal_status(status), "status {}", status);
+        }
+        for status in [4, 6] {
+            assert!(!is_terminal_status(status), "status {}", status);
+        }
+        // The order tracker agrees
+        for status in [1, 2, 4, 5, 6, 7] {
+            let state = OrderState::of(&test_order("1", "NSE:SBIN-EQ", 1, 10, status));
+            assert_eq!(state.is_terminal(), is_terminal_status(status));
+        }
+    }
+}
@@ -1,7 +1,10 @@
+use crate::credentials::Credentials;
 use crate::error::FyersError;
-use crate::models::user::{Profile, ProfileResponse};
+use crate::token_store::TokenStore;
+use crate::models::user::{LogoutResponse, Profile, ProfileResponse};
 use crate::models::{FundsResponse, HoldingsResponse};
 use reqwest::Client;
+use std::sync::Arc;
 
 const FYERS_API_BASE_URL: &str = "https://api-t1.fyers.in/api/v3";
 
@@ -9,8 +12,7 @@ const FYERS_API_BASE_URL: &str = "https://api-t1.fyers.in/api/v3";
 #[derive(Debug, Clone)]
 pub struct User {
     http_client: Client,
-    app_id: String,
-    access_token: String,
+    credentials: Credentials,
 }
 
 impl User {
@@ -23,8 +25,29 @@ impl User {
     pub fn new(app_id: String, access_token: String) -> Self {
         Self {
             http_client: Client::new(),
-            app_id,
-            access_token
+            credentials: Credentials::new(app_id, access_token),
+        }
+    }
+
+    /// # Description
+    /// Create a new instance of the User class that reads its access token from a token store.
+    /// Calls fail with an auth error once the stored token has expired.
+    ///
+    /// # Arguments
+    /// * `app_id` - The app id of the user.
+    /// * `token_store` - The token store holding the access token of the user.
+    pub fn from_token_store(app_id: String, token_store: Arc<dyn TokenStore>) -> Result<Self, FyersError> {
+        Ok(Self {
+            http_client: Client::new(),
+            credentials: Credentials::from_token_store(app_id, token_store)?,
+        })
+    }
+
+    /// Create a client that shares its credentials, and so its logout, with other clients
+    pub(crate) fn with_credentials(credentials: Credentials) -> Self {
+        Self {
+            http_client: Client::new(),
+            credentials,
         }
     }
 
@@ -32,7 +55,7 @@ impl User {
     /// Fetch user's profile information. [Read more](https://myapi.fyers.in/docsv3#tag/User/paths/~1User/post)
     pub async fn get_profile(&self) -> Result<Profile, FyersError> {
         let url = format!("{}/profile", FYERS_API_BASE_URL);
-        let auth_header_value = format!("{}:{}", self.app_id, self.access_token);
+        let auth_header_value = self.credentials.auth_header()?;
 
         let response = self
             .http_client
@@ -68,7 +91,7 @@ impl User {
     /// Get the balance available for the user for capital as well as the commodity market. [Read more](https://myapi.fyers.in/docsv3#tag/User/paths/~1User/put)
     pub async fn get_funds(&self) -> Result<FundsResponse, FyersError> {
         let url = format!("{}/funds", FYERS_API_BASE_URL);
-        let auth_header_value = format!("{}:{}", self.app_id, self.access_token);
+        let auth_header_value = self.credentials.auth_header()?;
         let response = self
             .http_client
             .get(&url)
@@ -98,7 +121,7 @@ impl User {
     /// include T1 and demat holdings. [Read more](https://myapi.fyers.in/docsv3#tag/User/paths/~1holdings/post)
     pub async fn get_holdings(&self) -> Result<HoldingsResponse, FyersError> {
         let url = format!("{}/holdings", FYERS_API_BASE_URL);
-        let auth_header_value = format!("{}:{}", self.app_id, self.access_token);
+        let auth_header_value = self.credentials.auth_header()?;
         let response = self
             .http_client
             .get(&url)
@@ -118,4 +141,36 @@ impl User {
             Err(FyersError::ApiError { s: holdings_response.s, code: holdings_response.code, message: holdings_response.message })
         }
     }
+
+    /// # Description
+    /// Log out and invalidate the access token. The token is also removed from the token store,
+    /// if the client was created with one, and every later call with the revoked token fails with
+    /// an auth error on this client, its clones and the clients sharing its credentials (those of
+    /// an [Account](crate::accounts::Account) or a [LiveBroker](crate::broker::LiveBroker)).
+    /// Clients created from a token store work again once a new login saves a new token to it.
+    ///
+    /// Clients created separately from the same access token are not told about the logout and
+    /// keep sending the revoked token. Clients created separately from the same token store stop
+    /// once they next read the store, within 30 seconds. [Read more](https://myapi.fyers.in/docsv3#tag/User/paths/~1logout/post)
+    pub async fn logout(&self) -> Result<(), FyersError> {
+        let url = format!("{}/logout", FYERS_API_BASE_URL);
+        let auth_header_value = self.credentials.auth_header()?;
+        let response = self
+            .http_client
+            .post(&url)
+            .header("Authorization", auth_header_value)
+            .send()
+            .await?;
+        // First check if API returned a non-success status code
+        if !response.status().is_success() {
+            return Err(FyersError::Network(response.error_for_status().unwrap_err()));
+        }
+        let response_text = response.text().await?;
+        let logout_response: LogoutResponse = serde_json::from_str(&response_text)?;
+        if logout_response.s == "ok" {
+            self.credentials.revoke()
+        } else {
+            Err(FyersError::ApiError { s: logout_response.s, code: logout_response.code, message: logout_response.message })
+        }
+    }
 }
@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+
+use crate::models::market_data::fyers_v1;
+use crate::models::Candle;
+
+/// Offset of Indian Standard Time from UTC, in seconds. Bars are aligned to IST midnight so that
+/// daily and intraday buckets line up with the exchange session.
+const IST_OFFSET_SECS: i64 = 19_800;
+
+/// The websocket sends prices as integers scaled by this factor.
+const DEFAULT_PRICE_DIVISOR: f64 = 100.0;
+
+/// A candle resolution, stored as the bar length in seconds. Always positive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Resolution(i64);
+
+impl Resolution {
+    /// One day, used to detect the start of a new session
+    const DAY: Resolution = Resolution(86_400);
+
+    /// # Description
+    /// A resolution of `n` seconds, or `None` unless `n` is positive
+    pub fn seconds(n: i64) -> Option<Self> {
+        (n > 0).then_some(Resolution(n))
+    }
+    /// A resolution of `n` minutes, or `None` unless `n` is positive
+    pub fn minutes(n: i64) -> Option<Self> {
+        n.checked_mul(60).and_then(Resolution::seconds)
+    }
+    /// A resolution of `n` days, or `None` unless `n` is positive
+    pub fn days(n: i64) -> Option<Self> {
+        n.checked_mul(86_400).and_then(Resolution::seconds)
+    }
+    pub fn as_secs(&self) -> i64 {
+        self.0
+    }
+
+    /// # Description
+    /// Parse a resolution in the format used by the history endpoint (e.g. "5S", "15", "60", "D", "1D")
+    pub fn from_fyers(resolution: &str) -> Option<Self> {
+        let resolution = resolution.trim().to_uppercase();
+        if let Some(secs) = resolution.strip_suffix('S') {
+            return secs.parse().ok().and_then(Resolution::seconds);
+        }
+        if let Some(days) = resolution.strip_suffix('D') {
+            if days.is_empty() {
+                return Some(Resolution::DAY);
+            }
+            return days.parse().ok().and_then(Resolution::days);
+        }
+        resolution.parse().ok().and_then(Resolution::minutes)
+    }
+
+    /// Start of the bar (epoch seconds) that contains `timestamp`
+    pub fn bucket_start(&self, timestamp: i64) -> i64 {
+        let local = timestamp + IST_OFFSET_SECS;
+        local - local.rem_euclid(self.0) - IST_OFFSET_SECS
+    }
+}
+
+/// An event produced by the [CandleBuilder] while consuming ticks
+#[derive(Debug, Clone, PartialEq)]
+pub enum CandleEvent {
+    /// The in-progress bar was updated by a tick
+    Updated { symbol: String, resolution: Resolution, candle: Candle },
+    /// A bar was completed, either because a tick for a later bar arrived or because of
+    /// [CandleBuilder::flush]
+    Closed { symbol: String, resolution: Resolution, candle: Candle },
+    /// A tick arrived for a bar that has already been closed. Its volume is counted in the
+    /// in-progress bar (if there is one), its price is ignored.
+    LateTick { symbol: String, resolution: Resolution, timestamp: i64, price: f64 },
+}
+
+/// Per symbol tick state
+#[derive(Debug, Default)]
+struct SymbolState {
+    last_vtt: Option<u64>,
+    // Session (IST day) in which `last_vtt` was seen
+    vtt_day: i64,
+    bars: HashMap<Resolution, Candle>,
+    // End of the most recently closed bar for each resolution
+    closed_until: HashMap<Resolution, i64>,
+}
+
+/// Builds OHLCV candles from market data websocket ticks.
+///
+/// Bars are bucketed by the exchange timestamp of the tick (`ltt`, falling back to the feed's
+/// `feed_time`) and volumes are derived from the difference in cumulative traded volume (`vtt`)
+/// between ticks, so missed ticks do not lose volume.
+#[derive(Debug)]
+pub struct CandleBuilder {
+    default_resolutions: Vec<Resolution>,
+    symbol_resolutions: HashMap<String, Vec<Resolution>>,
+    price_divisor: f64,
+    states: HashMap<String, SymbolState>,
+}
+
+impl CandleBuilder {
+    /// # Description
+    /// Create a new candle builder
+    ///
+    /// # Arguments
+    /// * `resolutions` - The resolutions to build for every symbol that does not have its own set
+    ///   through [CandleBuilder::set_resolutions]
+    pub fn new(resolutions: &[Resolution]) -> Self {
+        Self {
+            default_resolutions: resolutions.to_vec(),
+            symbol_resolutions: HashMap::new(),
+            price_divisor: DEFAULT_PRICE_DIVISOR,
+            states: HashMap::new(),
+        }
+    }
+
+    /// # Description
+    /// Override the resolutions built for a single symbol
+    ///
+    /// # Arguments
+    /// * `symbol` - The symbol ticker, e.g. "NSE:SBIN-EQ"
+    /// * `resolutions` - The resolutions to build for this symbol
+    pub fn set_resolutions(&mut self, symbol: &str, resolutions: &[Resolution]) {
+        self.symbol_resolutions.insert(symbol.to_string(), resolutions.to_vec());
+        if let Some(state) = self.states.get_mut(symbol) {
+            state.bars.retain(|res, _| resolutions.contains(res));
+        }
+    }
+
+    /// # Description
+    /// Set the factor websocket prices are divided by before they are used (defaults to 100)
+    pub fn with_price_divisor(mut self, divisor: f64) -> Self {
+        self.price_divisor = divisor;
+        self
+    }
+
+    /// # Description
+    /// Feed a decoded websocket message into the builder. Feeds without a quote are ignored.
+    ///
+    /// # Arguments
+    /// * `message` - A message received from [MarketDataSocket::listen](super::MarketDataSocket::listen)
+    pub fn on_message(&mut self, message: &fyers_v1::SocketMessage) -> Vec<CandleEvent> {
+        let mut events = Vec::new();
+        for (key, feed) in &message.feeds {
+            let Some(quote) = &feed.quote else { continue };
+            let Some(ltp) = quote.ltp else { continue };
+
+            let timestamp = match (quote.ltt, feed.feed_time) {
+                (Some(ltt), _) => ltt as i64,
+                // feed_time may be reported in milliseconds
+                (None, Some(ft)) if ft > 100_000_000_000 => (ft / 1000) as i64,
+                (None, Some(ft)) => ft as i64,
+                (None, None) => continue,
+            };
+            let symbol = if feed.ticker.is_empty() { key } else { &feed.ticker };
+            let price = ltp as f64 / self.price_divisor;
+
+            events.extend(self.on_tick(symbol, timestamp, price, quote.vtt));
+        }
+        events
+    }
+
+    /// # Description
+    /// Feed a single tick into the builder
+    ///
+    /// # Arguments
+    /// * `symbol` - The symbol ticker
+    /// * `timestamp` - Exchange timestamp of the trade, in epoch seconds
+    /// * `price` - Last traded price
+    /// * `cumulative_volume` - Total volume traded today (`vtt`), if known
+    pub fn on_tick(&mut self, symbol: &str, timestamp: i64, price: f64, cumulative_volume: Option<u64>) -> Vec<CandleEvent> {
+        let resolutions = self
+            .symbol_resolutions
+            .get(symbol)
+            .unwrap_or(&self.default_resolutions)
+            .clone();
+        let state = self.states.entry(symbol.to_string()).or_default();
+
+        // Volume is the change in cumulative volume. A lower value than we have seen in the same
+        // session is an out of order tick and adds nothing, a new session starts counting again.
+        let day = Resolution::DAY.bucket_start(timestamp);
+        let volume = match (state.last_vtt, cumulative_volume) {
+            (Some(_), Some(vtt)) if day > state.vtt_day => {
+                state.last_vtt = Some(vtt);
+                state.vtt_day = day;
+                vtt as i64
+            }
+            (Some(last), Some(vtt)) if vtt >= last => {
+                state.last_vtt = Some(vtt);
+                (vtt - last) as i64
+            }
+            (Some(_), Some(_)) => 0,
+            (None, Some(vtt)) => {
+                state.last_vtt = Some(vtt);
+                state.vtt_day = day;
+                0
+            }
+            (_, None) => 0,
+        };
+
+        let mut events = Vec::new();
+        for resolution in resolutions {
+            let start = resolution.bucket_start(timestamp);
+            let closed_until = state.closed_until.get(&resolution).copied().unwrap_or(i64::MIN);
+            match state.bars.get_mut(&resolution) {
+                Some(bar) if start == bar.timestamp() => {
+                    bar.2 = bar.2.max(price);
+                    bar.3 = bar.3.min(price);
+                    bar.4 = price;
+                    bar.5 += volume;
+                }
+                Some(bar) if start < bar.timestamp() => {
+                    bar.5 += volume;
+                    events.push(CandleEvent::LateTick {
+                        symbol: symbol.to_string(),
+                        resolution,
+                        timestamp,
+                        price,
+                    });
+                    continue;
+                }
+                Some(bar) => {
+                    events.push(CandleEvent::Closed {
+                        symbol: symbol.to_string(),
+                        resolution,
+                        candle: *bar,
+                    });
+                    state.closed_until.insert(resolution, bar.timestamp() + resolution.as_secs());
+                    *bar = Candle(start, price, price, price, price, volume);
+                }
+                None if start < closed_until => {
+                    events.push(CandleEvent::LateTick {
+                        symbol: symbol.to_string(),
+                        resolution,
+                        timestamp,
+                        price,
+                    });
+                    continue;
+                }
+                None => {
+                    state.bars.insert(resolution, Candle(start, price, price, price, price, volume));
+                }
+            }
+            events.push(CandleEvent::Updated {
+                symbol: symbol.to_string(),
+                resolution,
+                candle: state.bars[&resolution],
+            });
+        }
+        events
+    }
+
+    /// # Description
+    /// Close every in-progress bar that ends at or before `now`. Use this on a timer so that bars
+    /// close on time for illiquid symbols that have no tick in the next bar.
+    ///
+    /// # Arguments
+    /// * `now` - The current time, in epoch seconds
+    pub fn flush(&mut self, now: i64) -> Vec<CandleEvent> {
+        let mut events = Vec::new();
+        for (symbol, state) in self.states.iter_mut() {
+            let closed_until = &mut state.closed_until;
+            state.bars.retain(|resolution, bar| {
+                let end = bar.timestamp() + resolution.as_secs();
+                if end <= now {
+                    closed_until.insert(*resolution, end);
+                    events.push(CandleEvent::Closed {
+                        symbol: symbol.clone(),
+                        resolution: *resolution,
+                        candle: *bar,
+                    });
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+        events
+    }
+
+    /// # Description
+    /// Get the in-progress bar for a symbol and resolution
+    pub fn current(&self, symbol: &str, resolution: Resolution) -> Option<&Candle> {
+        self.states.get(symbol).and_then(|state| state.bars.get(&resolution))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 05-Sep-2025 09:15:00 IST
+    const OPEN: i64 = 1_757_043_900;
+
+    #[test]
+    fn resolutions_must_be_positive() {
+        assert_eq!(Resolution::seconds(0), None);
+        assert_eq!(Resolution::minutes(-1), None);
+        assert_eq!(Resolution::days(i64::MAX), None);
+        assert_eq!(Resolution::minutes(5).map(|r| r.as_secs()), Some(300));
+    }
+
+    #[test]
+    fn parses_fyers_resolutions() {
+        assert_eq!(Resolution::from_fyers("5S"), Resolution::seconds(5));
+        assert_eq!(Resolution::from_fyers("15"), Resolution::minutes(15));
+        assert_eq!(Resolution::from_fyers("D"), Resolution::days(1));
+        assert_eq!(Resolution::from_fyers("1D"), Resolution::days(1));
+        assert_eq!(Resolution::from_fyers("0"), None);
+        assert_eq!(Resolution::from_fyers("0S"), None);
+        assert_eq!(Resolution::from_fyers("abc"), None);
+    }
+
+    #[test]
+    fn buckets_align_to_ist() {
+        let minute = Resolution::minutes(1).unwrap();
+        assert_eq!(minute.bucket_start(OPEN + 59), OPEN);
+        let day = Resolution::days(1).unwrap();
+        // 05-Sep-2025 00:00 IST
+        assert_eq!(day.bucket_start(OPEN), OPEN - (9 * 3600 + 15 * 60));
+    }
+
+    #[test]
+    fn builds_and_closes_bars() {
+        let minute = Resolution::minutes(1).unwrap();
+        let mut builder = CandleBuilder::new(&[minute]);
+        builder.on_tick("NSE:SBIN-EQ", OPEN, 100.0, Some(1_000));
+        builder.on_tick("NSE:SBIN-EQ", OPEN + 10, 102.0, Some(1_050));
+        builder.on_tick("NSE:SBIN-EQ", OPEN + 20, 99.0, Some(1_080));
+        let events = builder.on_tick("NSE:SBIN-EQ", OPEN + 60, 101.0, Some(1_100));
+
+        let closed = events.iter().find_map(|event| match event {
+            CandleEvent::Closed { candle, .. } => Some(*candle),
+            _ => None,
+        });
+        assert_eq!(closed, Some(Candle(OPEN, 100.0, 102.0, 99.0, 99.0, 80)));
+        assert_eq!(builder.current("NSE:SBIN-EQ", minute), Some(&Candle(OPEN + 60, 101.0, 101.0, 101.0, 101.0, 20)));
+    }
+
+    #[test]
+    fn late_ticks_keep_their_volume() {
+        let minute = Resolution::minutes(1).unwrap();
+        let mut builder = CandleBuilder::new(&[minute]);
+        builder.on_tick("NSE:SBIN-EQ", OPEN, 100.0, Some(1_000));
+        builder.on_tick("NSE:SBIN-EQ", OPEN + 60, 101.0, Some(1_010));
+        let events = builder.on_tick("NSE:SBIN-EQ", OPEN + 30, 95.0, Some(1_015));
+
+        assert!(matches!(events.as_slice(), [CandleEvent::LateTick { .. }]));
+        assert_eq!(builder.current("NSE:SBIN-EQ", minute), Some(&Candle(OPEN + 60, 101.0, 101.0, 101.0, 101.0, 15)));
+    }
+
+    #[test]
+    fn flush_closes_finished_bars() {
+        let minute = Resolution::minutes(1).unwrap();
+        let mut builder = CandleBuilder::new(&[minute]);
+        builder.on_tick("NSE:SBIN-EQ", OPEN, 100.0, None);
+
+        assert!(builder.flush(OPEN + 59).is_empty());
+        assert_eq!(builder.flush(OPEN + 60).len(), 1);
+        assert_eq!(builder.current("NSE:SBIN-EQ", minute), None);
+        let events = builder.on_tick("NSE:SBIN-EQ", OPEN + 5, 100.0, None);
+        assert!(matches!(events.as_slice(), [CandleEvent::LateTick { .. }]));
+    }
+}
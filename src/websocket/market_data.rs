@@ -1,4 +1,6 @@
+use crate::credentials::Credentials;
 use crate::error::FyersError;
+use crate::token_store::TokenStore;
 use crate::models::market_data::{fyers_v1};
 use crate::models::websocket::{SubscriptionData, SubscriptionRequest, SubscriptionMode};
 use serde::Serialize;
@@ -14,6 +16,7 @@ use tokio_tungstenite::{
 };
 use prost::Message as ProstMessage;
 use reqwest::Client;
+use std::sync::Arc;
 
 type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
 
@@ -21,8 +24,7 @@ const MARKET_DATA_URL: &str = "wss://socket.fyers.in/hsm/v1-5/prod";
 const TBTWS_URL_REST_ENDPOINT: &str = "https://api-t1.fyers.in/indus/home/tbtws";
 
 pub struct MarketDataSocket {
-    app_id: String,
-    access_token: String,
+    credentials: Credentials,
     http_client: Client,
     stream: Option<WsStream>,
 }
@@ -38,13 +40,27 @@ impl MarketDataSocket {
     /// * `access_token` - The access token of the user.
     pub fn new(app_id: String, access_token: String) -> Self {
         Self {
-            app_id,
-            access_token,
+            credentials: Credentials::new(app_id, access_token),
             http_client: Client::new(),
             stream: None,
         }
     }
 
+    /// # Description
+    /// Create a new market data socket that reads its access token from a token store. Connecting
+    /// fails with an auth error once the stored token has expired.
+    ///
+    /// # Arguments
+    /// * `app_id` - The app id of the user.
+    /// * `token_store` - The token store holding the access token of the user.
+    pub fn from_token_store(app_id: String, token_store: Arc<dyn TokenStore>) -> Result<Self, FyersError> {
+        Ok(Self {
+            credentials: Credentials::from_token_store(app_id, token_store)?,
+            http_client: Client::new(),
+            stream: None,
+        })
+    }
+
     /// # Description
     /// Connect to the Market Data Websocket endpoint
     pub async fn connect(&mut self) -> Result<(), FyersError> {
@@ -52,7 +68,7 @@ impl MarketDataSocket {
             return Err(FyersError::WebSocket("Client is already connected.".to_string()));
         }
 
-        let auth_token = format!("{}:{}", self.app_id, self.access_token);
+        let auth_token = self.credentials.auth_header()?;
         let url = url::Url::parse(MARKET_DATA_URL).expect("Failed to parse market data url");
         let host = url
             .host_str()
@@ -1,5 +1,7 @@
 pub mod market_data;
 pub mod general_socket;
+pub mod candle_builder;
 
 pub use market_data::MarketDataSocket;
 pub use general_socket::GeneralSocket;
+pub use candle_builder::{CandleBuilder, CandleEvent, Resolution};